@@ -0,0 +1,94 @@
+use plotters::{
+  coord::Shift,
+  prelude::{
+    BitMapBackend, DrawingArea, DrawingAreaErrorKind, DrawingBackend,
+    IntoDrawingArea, SVGBackend,
+  },
+};
+use std::{error::Error, fmt::Display, path::Path};
+
+pub mod distribution_chart;
+#[cfg(feature = "perf")]
+pub mod icicle_chart;
+pub mod line_chart;
+pub mod phase_chart;
+
+pub use self::distribution_chart::DistributionChart;
+#[cfg(feature = "perf")]
+pub use self::icicle_chart::PerfChart;
+pub use self::line_chart::{
+  chart_groups, Autoscale, ChartGroup, ChartOptions, PlotArea, SourceChart,
+  SourceCharts, StatsChart, StatsCharts, TickRange,
+};
+pub use self::phase_chart::PhaseChart;
+
+pub trait Chart {
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>>;
+
+  fn render_png<P: AsRef<Path>>(
+    &self,
+    path: P,
+    size: (u32, u32),
+  ) -> Result<(), ChartError> {
+    let root = BitMapBackend::new(path.as_ref(), size).into_drawing_area();
+    self.draw(&root)?;
+    root.present()?;
+    Ok(())
+  }
+
+  fn render_svg<P: AsRef<Path>>(
+    &self,
+    path: P,
+    size: (u32, u32),
+  ) -> Result<(), ChartError> {
+    let root = SVGBackend::new(path.as_ref(), size).into_drawing_area();
+    self.draw(&root)?;
+    root.present()?;
+    Ok(())
+  }
+
+  fn render_svg_string(&self, size: (u32, u32)) -> Result<String, ChartError> {
+    let mut svg = String::new();
+    {
+      let root = SVGBackend::with_string(&mut svg, size).into_drawing_area();
+      self.draw(&root)?;
+      root.present()?;
+    }
+    Ok(svg)
+  }
+
+  /// Renders into `buffer`, which must hold `size.0 * size.1` RGB pixels.
+  fn render_rgb(
+    &self,
+    buffer: &mut [u8],
+    size: (u32, u32),
+  ) -> Result<(), ChartError> {
+    let root = BitMapBackend::with_buffer(buffer, size).into_drawing_area();
+    self.draw(&root)?;
+    root.present()?;
+    Ok(())
+  }
+}
+
+#[derive(Debug)]
+pub struct ChartError(String);
+
+impl Display for ChartError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Problem rendering chart: {}", self.0)
+  }
+}
+
+impl Error for ChartError {}
+
+impl<E> From<DrawingAreaErrorKind<E>> for ChartError
+where
+  E: Error + Send + Sync,
+{
+  fn from(e: DrawingAreaErrorKind<E>) -> Self {
+    ChartError(e.to_string())
+  }
+}
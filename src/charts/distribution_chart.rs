@@ -0,0 +1,353 @@
+use crate::stats::{
+  DistributionDescriptor, DistributionView, Histogram, StatisticsSource, Unit,
+};
+use std::{cell::RefCell, ops::Range};
+
+use plotters::{
+  coord::Shift,
+  prelude::{
+    ChartBuilder, DrawingArea, DrawingAreaErrorKind, DrawingBackend,
+    LineSeries, Palette99, Polygon, Rectangle, ViridisRGB,
+  },
+  style::{Color as PlottersColor, IntoFont, Palette},
+};
+
+use super::{
+  line_chart::{
+    draw_annotations, time_description, time_label, visible_samples, PixelRect,
+  },
+  Chart, PlotArea, TickRange,
+};
+
+/// The most columns drawn in a heatmap, so long histories stay cheap.
+const MAX_HEATMAP_COLUMNS: usize = 500;
+
+/// Percentile bands of the fan chart, outermost first.
+const FAN_BANDS: [(f64, f64); 3] = [(0.05, 0.95), (0.1, 0.9), (0.25, 0.75)];
+
+/// A chart of one distribution group of any `StatisticsSource`.
+pub struct DistributionChart<'a> {
+  source: &'a dyn StatisticsSource,
+  group: usize,
+  view: DistributionView,
+  tick_range: TickRange,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a> DistributionChart<'a> {
+  pub fn new(source: &'a dyn StatisticsSource, group: usize) -> Self {
+    Self {
+      source,
+      group,
+      view: source.distribution_groups()[group].view,
+      tick_range: TickRange::All,
+      plot_areas: None,
+    }
+  }
+
+  pub fn view(mut self, view: DistributionView) -> Self {
+    self.view = view;
+    self
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  /// Appends this chart's plot area to `plot_areas` while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+
+  fn descriptor(&self) -> &'a DistributionDescriptor {
+    &self.source.distribution_groups()[self.group]
+  }
+
+  /// The unit to label values spanning `values` in, prefixed to suit them,
+  /// and the label of a recorded value. Values are plotted as recorded.
+  fn value_labels(
+    &self,
+    values: &Range<f64>,
+  ) -> (Unit, impl Fn(&f64) -> String + '_) {
+    let group = self.descriptor();
+    let shown = &group.display_unit;
+    let axis_unit = shown.axis_unit(
+      group.shown_value(values.start),
+      group.shown_value(values.end),
+    );
+    let unit = axis_unit.clone();
+    let label = move |value: &f64| {
+      let value = group.shown_value(*value);
+      unit.axis_label(shown.convert(value, &unit).unwrap_or(value))
+    };
+    (axis_unit, label)
+  }
+
+  /// Pushes the plot area of a view, whose x axis is ticks unless it shows
+  /// `histogram`.
+  fn push_plot_area(
+    &self,
+    pixels: PixelRect,
+    ticks: Range<usize>,
+    values: Range<f64>,
+    histogram: Option<&Histogram>,
+  ) {
+    if let Some(plot_areas) = self.plot_areas {
+      plot_areas.borrow_mut().push(PlotArea {
+        pixels,
+        ticks,
+        values,
+        x_values: histogram.map(|histogram| histogram.min..histogram.max),
+        histogram: histogram.cloned(),
+        legend: vec![],
+      });
+    }
+  }
+}
+
+impl<'a> Chart for DistributionChart<'a> {
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let (_, ys) = drawing_area.get_pixel_range();
+    let h = (ys.end - ys.start) as f64;
+
+    let source = self.source;
+    // Nothing has been recorded yet during warm-up
+    if source.is_empty() {
+      return Ok(());
+    }
+    let (first, last) = (source.tick(0), source.tick(source.len() - 1));
+    let mut ticks = self.tick_range.resolve(first, last);
+    if ticks.end <= ticks.start {
+      ticks.end = ticks.start + 1;
+    }
+    let histograms: Vec<_> = visible_samples(source, &ticks)
+      .map(|sample| (source.tick(sample), source.histogram(sample, self.group)))
+      .collect();
+    let latest = histograms
+      .iter()
+      .rev()
+      .find(|(tick, _)| *tick <= ticks.end)
+      .or_else(|| histograms.first());
+    let (latest_tick, latest) = match latest {
+      Some((tick, histogram)) => (*tick, histogram),
+      None => return Ok(()),
+    };
+
+    let title = &self.descriptor().title;
+    let caption = match self.view {
+      DistributionView::Histogram => {
+        format!("{} (tick {})", title, latest_tick)
+      }
+      _ => title.clone(),
+    };
+    let mut builder = ChartBuilder::on(drawing_area);
+    builder
+      .margin(10)
+      .caption(
+        caption,
+        ("sans-serif", h / 15.0)
+          .into_font()
+          .color(&plotters::prelude::WHITE),
+      )
+      .x_label_area_size(40)
+      .y_label_area_size(50);
+    let label_style = ("sans-serif", h / 30.0)
+      .into_font()
+      .color(&plotters::prelude::WHITE);
+    let (axis_unit, value_label) = self.value_labels(&(latest.min..latest.max));
+
+    match self.view {
+      DistributionView::Histogram => {
+        let max_count = latest.counts.iter().copied().fold(0.0, f64::max);
+        let max_count = if max_count > 0.0 {
+          max_count * 1.05
+        } else {
+          1.0
+        };
+        let mut cc =
+          builder.build_cartesian_2d(latest.min..latest.max, 0.0..max_count)?;
+
+        cc.configure_mesh()
+          .x_label_formatter(&value_label)
+          .x_desc(&axis_unit.symbol)
+          .y_desc("count")
+          .label_style(label_style.clone())
+          .axis_desc_style(label_style)
+          .axis_style(plotters::prelude::WHITE.to_rgba())
+          .bold_line_style(plotters::prelude::WHITE.mix(0.5))
+          .light_line_style(plotters::prelude::WHITE.mix(0.25))
+          .draw()?;
+
+        cc.draw_series(latest.counts.iter().enumerate().map(
+          |(bin, count)| {
+            let range = latest.bin_range(bin);
+            Rectangle::new(
+              [(range.start, 0.0), (range.end, *count)],
+              Palette99::pick(0).filled(),
+            )
+          },
+        ))?;
+
+        self.push_plot_area(
+          cc.plotting_area().get_pixel_range(),
+          ticks,
+          0.0..max_count,
+          Some(latest),
+        );
+      }
+      DistributionView::Heatmap => {
+        let mut cc = builder.build_cartesian_2d(
+          ticks.start as u32..ticks.end as u32,
+          latest.min..latest.max,
+        )?;
+
+        configure_time_mesh(
+          &mut cc,
+          source,
+          &axis_unit.symbol,
+          &value_label,
+          &ticks,
+          label_style,
+        )?;
+
+        let stride = (histograms.len() / MAX_HEATMAP_COLUMNS).max(1);
+        let columns: Vec<_> = histograms.iter().step_by(stride).collect();
+        for (i, (tick, histogram)) in columns.iter().enumerate() {
+          let next_tick = columns
+            .get(i + 1)
+            .map_or(ticks.end.max(*tick + 1), |(next, _)| *next);
+          cc.draw_series(heatmap_column(*tick, next_tick, histogram))?;
+        }
+        draw_annotations(
+          &mut cc,
+          source.annotations(),
+          &ticks,
+          &(latest.min..latest.max),
+          h / 30.0,
+        )?;
+
+        self.push_plot_area(
+          cc.plotting_area().get_pixel_range(),
+          ticks,
+          latest.min..latest.max,
+          None,
+        );
+      }
+      DistributionView::PercentileFan => {
+        let quantiles = |q: f64| -> Vec<(u32, f64)> {
+          histograms
+            .iter()
+            .map(|(tick, histogram)| (*tick as u32, histogram.quantile(q)))
+            .filter(|(_, value)| value.is_finite())
+            .collect()
+        };
+        let mut cc = builder.build_cartesian_2d(
+          ticks.start as u32..ticks.end as u32,
+          latest.min..latest.max,
+        )?;
+
+        configure_time_mesh(
+          &mut cc,
+          source,
+          &axis_unit.symbol,
+          &value_label,
+          &ticks,
+          label_style,
+        )?;
+
+        let color = Palette99::pick(0);
+        for (lo, hi) in FAN_BANDS.iter() {
+          let mut band = quantiles(*lo);
+          band.extend(quantiles(*hi).into_iter().rev());
+          cc.draw_series(std::iter::once(Polygon::new(band, color.mix(0.25))))?;
+        }
+        cc.draw_series(LineSeries::new(quantiles(0.5), color.stroke_width(2)))?;
+        draw_annotations(
+          &mut cc,
+          source.annotations(),
+          &ticks,
+          &(latest.min..latest.max),
+          h / 30.0,
+        )?;
+
+        self.push_plot_area(
+          cc.plotting_area().get_pixel_range(),
+          ticks,
+          latest.min..latest.max,
+          None,
+        );
+      }
+    }
+
+    Ok(())
+  }
+}
+
+fn configure_time_mesh<DB: DrawingBackend>(
+  cc: &mut plotters::chart::ChartContext<
+    DB,
+    plotters::coord::cartesian::Cartesian2d<
+      plotters::coord::types::RangedCoordu32,
+      plotters::coord::types::RangedCoordf64,
+    >,
+  >,
+  source: &dyn StatisticsSource,
+  unit: &str,
+  value_label: &dyn Fn(&f64) -> String,
+  ticks: &Range<usize>,
+  label_style: plotters::style::TextStyle,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+  cc.configure_mesh()
+    .x_label_formatter(&|x| {
+      let tick = *x as usize;
+      time_label(
+        source.time_axis(),
+        &|tick| source.elapsed_seconds(tick),
+        tick,
+        ticks,
+        source.shown_time(tick),
+      )
+    })
+    .x_labels(10)
+    .y_labels(10)
+    .x_desc(time_description(
+      source.time_axis(),
+      source.elapsed_seconds(0).is_some(),
+      &source.display_time_unit(),
+    ))
+    .y_label_formatter(value_label)
+    .y_desc(unit)
+    .label_style(label_style.clone())
+    .axis_desc_style(label_style)
+    .axis_style(plotters::prelude::WHITE.to_rgba())
+    .bold_line_style(plotters::prelude::WHITE.mix(0.5))
+    .light_line_style(plotters::prelude::WHITE.mix(0.25))
+    .draw()
+}
+
+/// One column of the heatmap, each bin shaded by its share of the sample's
+/// largest bin.
+fn heatmap_column(
+  tick: usize,
+  next_tick: usize,
+  histogram: &Histogram,
+) -> impl Iterator<Item = Rectangle<(u32, f64)>> + '_ {
+  let max_count = histogram.counts.iter().copied().fold(0.0, f64::max);
+  histogram
+    .counts
+    .iter()
+    .enumerate()
+    .filter(|(_, count)| **count > 0.0)
+    .map(move |(bin, count)| {
+      let range = histogram.bin_range(bin);
+      Rectangle::new(
+        [(tick as u32, range.start), (next_tick as u32, range.end)],
+        ViridisRGB::get_color(count / max_count).filled(),
+      )
+    })
+}
@@ -2,19 +2,17 @@ use std::time::Duration;
 
 use plotters::{
   coord::Shift,
-  prelude::{
-    BitMapBackend, DrawingArea, DrawingAreaErrorKind, DrawingBackend, Rectangle,
-  },
+  prelude::{DrawingArea, DrawingAreaErrorKind, DrawingBackend, Rectangle},
   style::{Color, IntoFont, Palette, Palette99, BLACK, WHITE},
 };
 
 use crate::perf::{FoldedSpan, FoldedSpans};
 
-use super::PlottersDrawableAdapter;
+use super::Chart;
 
 const LAYER_HEIGHT: i32 = 40;
 
-pub(crate) struct PerfChart<'a> {
+pub struct PerfChart<'a> {
   perf: &'a FoldedSpans,
 }
 
@@ -24,14 +22,11 @@ impl<'a> PerfChart<'a> {
   }
 }
 
-impl<'a> PlottersDrawableAdapter for PerfChart<'a> {
-  fn draw(
+impl<'a> Chart for PerfChart<'a> {
+  fn draw<DB: DrawingBackend>(
     &self,
-    da: &DrawingArea<BitMapBackend, Shift>,
-  ) -> Result<
-    (),
-    DrawingAreaErrorKind<<BitMapBackend as DrawingBackend>::ErrorType>,
-  > {
+    da: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
     da.fill(&plotters::prelude::BLACK)?;
 
     let (xs, ys) = da.get_pixel_range();
@@ -146,7 +141,7 @@ fn draw_span_header<DB: DrawingBackend>(
   Ok(())
 }
 
-pub struct HumanReadableDuration<'a>(&'a Duration);
+pub struct HumanReadableDuration<'a>(pub &'a Duration);
 
 impl<'a> std::fmt::Display for HumanReadableDuration<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,1119 @@
+use crate::stats::{
+  source::shown_time_unit, Annotation, DistributionView, GroupDescriptor,
+  Histogram, PhaseColoring, SimStats, Statistics, StatisticsGroup,
+  StatisticsSource, TimeAxis, Unit,
+};
+use std::{cell::RefCell, collections::BTreeSet, ops::Range};
+
+use plotters::{
+  chart::{ChartContext, SeriesLabelPosition},
+  coord::{
+    cartesian::Cartesian2d,
+    types::{RangedCoordf64, RangedCoordu32},
+    Shift,
+  },
+  prelude::{
+    ChartBuilder, DrawingArea, DrawingAreaErrorKind, DrawingBackend,
+    EmptyElement, LineSeries, Palette99, PathElement, Rectangle, Text,
+  },
+  style::{Color as PlottersColor, IntoFont, Palette},
+};
+
+use super::{Chart, DistributionChart, PhaseChart};
+
+/// Which ticks are shown along the x axis of a chart.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TickRange {
+  /// From the first to the last recorded tick.
+  #[default]
+  All,
+  /// A fixed window of ticks.
+  Fixed(Range<usize>),
+  /// A sliding window over the most recent ticks.
+  Latest(usize),
+}
+
+impl TickRange {
+  pub fn resolve(&self, first: usize, last: usize) -> Range<usize> {
+    match self {
+      TickRange::All => first..last,
+      TickRange::Fixed(range) => range.clone(),
+      TickRange::Latest(n) => last.saturating_sub(*n).max(first)..last,
+    }
+  }
+
+  /// The range after dragging a plot `dx` pixels, for samples from `first`
+  /// to `last`. `carry` keeps the part of a tick left over between drags.
+  pub fn panned(
+    &self,
+    first: usize,
+    last: usize,
+    area: &PlotArea,
+    dx: f32,
+    carry: &mut f64,
+  ) -> TickRange {
+    let ticks = self.resolve(first, last);
+    let (xs, _) = &area.pixels;
+    let ticks_per_px =
+      (ticks.end - ticks.start) as f64 / (xs.end - xs.start).max(1) as f64;
+
+    let shift = *carry - dx as f64 * ticks_per_px;
+    *carry = shift.fract();
+    let shift = shift.trunc() as i64;
+
+    let start = (ticks.start as i64 + shift).max(0);
+    let end = start + (ticks.end - ticks.start) as i64;
+    TickRange::Fixed(start as usize..end as usize)
+  }
+
+  /// The range after `steps` steps of the mouse wheel at pixel `x` of a plot.
+  pub fn zoomed(
+    &self,
+    first: usize,
+    last: usize,
+    area: &PlotArea,
+    x: i32,
+    steps: f32,
+  ) -> TickRange {
+    let factor = 0.9f64.powf(steps as f64);
+    match self {
+      TickRange::Latest(n) => {
+        TickRange::Latest(((*n as f64 * factor).round() as usize).max(2))
+      }
+      _ => {
+        let ticks = self.resolve(first, last);
+        let anchor = area.tick_at(x);
+        let start = anchor - (anchor - ticks.start as f64) * factor;
+        let end = anchor + (ticks.end as f64 - anchor) * factor;
+        let start = start.round().max(0.0) as usize;
+        let end = (end.round() as usize).max(start + 2);
+        TickRange::Fixed(start..end)
+      }
+    }
+  }
+}
+
+/// What the y axis of a chart is fitted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Autoscale {
+  /// Every recorded sample.
+  #[default]
+  History,
+  /// Only the samples inside the visible tick range.
+  Window,
+}
+
+/// Per-chart display options.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChartOptions {
+  /// Indices into the group's names of the series that aren't drawn.
+  pub hidden: BTreeSet<usize>,
+  pub log_scale: bool,
+  pub autoscale: Autoscale,
+  /// Stretch the y axis to include zero rather than fitting it to the data.
+  pub anchor_zero: bool,
+  /// Ignore this fraction of samples at each end when fitting the y axis, so
+  /// that a few outliers don't flatten the rest of a noisy series.
+  pub clip_percentile: Option<f64>,
+  /// Overrides the view of a distribution group.
+  pub distribution_view: Option<DistributionView>,
+  /// Overrides the colouring of a phase group.
+  pub phase_coloring: Option<PhaseColoring>,
+  /// Outlines the chart, such as when an alert fires on one of its series.
+  pub highlight: bool,
+}
+
+impl ChartOptions {
+  pub fn toggle_series(&mut self, series: usize) {
+    if !self.hidden.remove(&series) {
+      self.hidden.insert(series);
+    }
+  }
+
+  pub fn toggle_log_scale(&mut self) {
+    self.log_scale = !self.log_scale;
+  }
+
+  pub fn toggle_anchor_zero(&mut self) {
+    self.anchor_zero = !self.anchor_zero;
+  }
+
+  /// Toggles clipping the top and bottom `percentile` of samples.
+  pub fn toggle_clip_percentile(&mut self, percentile: f64) {
+    self.clip_percentile = match self.clip_percentile {
+      Some(_) => None,
+      None => Some(percentile),
+    };
+  }
+
+  /// Moves a distribution chart on to its next view.
+  pub fn cycle_distribution_view(&mut self, default: DistributionView) {
+    self.distribution_view =
+      Some(self.distribution_view.unwrap_or(default).next());
+  }
+
+  /// Switches a phase chart between its colourings.
+  pub fn cycle_phase_coloring(&mut self, default: PhaseColoring) {
+    self.phase_coloring = Some(self.phase_coloring.unwrap_or(default).next());
+  }
+
+  pub fn toggle_autoscale(&mut self) {
+    self.autoscale = match self.autoscale {
+      Autoscale::History => Autoscale::Window,
+      Autoscale::Window => Autoscale::History,
+    };
+  }
+}
+
+/// Pixel bounds along x and y.
+pub type PixelRect = (Range<i32>, Range<i32>);
+
+/// Where a chart's plot landed in pixels, so that pixels can be mapped back
+/// to ticks and values after drawing.
+#[derive(Clone, Debug)]
+pub struct PlotArea {
+  pub pixels: PixelRect,
+  pub ticks: Range<usize>,
+  pub values: Range<f64>,
+  /// The x axis values, for charts whose x axis isn't ticks.
+  pub x_values: Option<Range<f64>>,
+  /// The histogram of a histogram view, whose x axis is its bins, so the
+  /// chart doesn't pan or zoom through ticks.
+  pub histogram: Option<Histogram>,
+  /// The pixel bounds of each series' legend entry.
+  pub legend: Vec<(usize, PixelRect)>,
+}
+
+impl PlotArea {
+  pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+    self.pixels.0.contains(&x) && self.pixels.1.contains(&y)
+  }
+
+  pub fn legend_entry_at(&self, (x, y): (i32, i32)) -> Option<usize> {
+    self
+      .legend
+      .iter()
+      .find(|(_, (xs, ys))| xs.contains(&x) && ys.contains(&y))
+      .map(|(series, _)| *series)
+  }
+
+  pub fn tick_at(&self, x: i32) -> f64 {
+    let (xs, _) = &self.pixels;
+    let t = (x - xs.start) as f64 / (xs.end - xs.start).max(1) as f64;
+    self.ticks.start as f64
+      + t * (self.ticks.end as f64 - self.ticks.start as f64)
+  }
+
+  pub fn x_of_tick(&self, tick: usize) -> i32 {
+    let (xs, _) = &self.pixels;
+    let span = (self.ticks.end as f64 - self.ticks.start as f64).max(1.0);
+    let t = (tick as f64 - self.ticks.start as f64) / span;
+    xs.start + (t * (xs.end - xs.start) as f64) as i32
+  }
+
+  /// The x axis value at a pixel of a chart with `x_values`.
+  pub fn x_value_at(&self, x: i32) -> Option<f64> {
+    let x_values = self.x_values.as_ref()?;
+    let (xs, _) = &self.pixels;
+    let t = (x - xs.start) as f64 / (xs.end - xs.start).max(1) as f64;
+    Some(x_values.start + t * (x_values.end - x_values.start))
+  }
+
+  /// The pixel of a point on a chart with `x_values`.
+  pub fn pixel_of(&self, x: f64, y: f64) -> Option<(i32, i32)> {
+    let x_values = self.x_values.as_ref()?;
+    let (xs, ys) = &self.pixels;
+    let tx = (x - x_values.start) / (x_values.end - x_values.start);
+    let ty = (y - self.values.start) / (self.values.end - self.values.start);
+    Some((
+      xs.start + (tx * (xs.end - xs.start) as f64) as i32,
+      ys.end - (ty * (ys.end - ys.start) as f64) as i32,
+    ))
+  }
+}
+
+pub struct StatsCharts<'a, TState, TStatistics: Statistics<TState>> {
+  stats: &'a SimStats<TState, TStatistics>,
+  tick_range: TickRange,
+  options: &'a [ChartOptions],
+  baseline: Option<&'a dyn StatisticsSource>,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>>
+  StatsCharts<'a, TState, TStatistics>
+{
+  pub fn new(stats: &'a SimStats<TState, TStatistics>) -> Self {
+    Self {
+      stats,
+      tick_range: TickRange::All,
+      options: &[],
+      baseline: None,
+      plot_areas: None,
+    }
+  }
+
+  /// Overlays a saved run on the line charts, matching groups by title.
+  pub fn baseline(mut self, baseline: &'a dyn StatisticsSource) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+
+  /// Options for each chart, in the order line groups, distribution groups
+  /// then phase groups. Missing charts use defaults.
+  pub fn options(mut self, options: &'a [ChartOptions]) -> Self {
+    self.options = options;
+    self
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  /// Collects the plot area of each chart, in the same order as `options`,
+  /// while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>> Chart
+  for StatsCharts<'a, TState, TStatistics>
+{
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let sources: [&dyn StatisticsSource; 1] = [self.stats];
+    let mut charts = SourceCharts::new(&sources)
+      .tick_range(self.tick_range.clone())
+      .options(self.options);
+    if let Some(baseline) = self.baseline {
+      charts = charts.baseline(baseline);
+    }
+    if let Some(plot_areas) = self.plot_areas {
+      charts = charts.plot_areas(plot_areas);
+    }
+    charts.draw(drawing_area)
+  }
+}
+
+/// The rows and columns that fit `n` charts into `w` by `h` pixels with
+/// cells closest to square.
+fn grid(w: i32, h: i32, n: usize) -> (usize, usize) {
+  (1..=n.max(1))
+    .map(|r| (r, (n + r - 1) / r))
+    .min_by(|(r1, c1), (r2, c2)| {
+      let (w1, h1) = (w as f64 / *c1 as f64, h as f64 / *r1 as f64);
+      let (w2, h2) = (w as f64 / *c2 as f64, h as f64 / *r2 as f64);
+      let a1 = w1 / h1;
+      let a2 = w2 / h2;
+      a1.ln().abs().partial_cmp(&a2.ln().abs()).unwrap()
+    })
+    .unwrap()
+}
+
+/// A time label to three decimal places, without trailing zeros.
+pub(super) fn tidy(value: f64) -> String {
+  let label = format!("{:.3}", value);
+  let label = label.trim_end_matches('0').trim_end_matches('.');
+  if label == "-0" {
+    "0".into()
+  } else {
+    label.into()
+  }
+}
+
+/// The label of a tick on a time axis showing `ticks`: a date or duration
+/// when the axis has them and `elapsed` maps ticks to seconds, or else the
+/// shown time.
+pub(super) fn time_label(
+  axis: TimeAxis,
+  elapsed: &dyn Fn(usize) -> Option<f64>,
+  tick: usize,
+  ticks: &Range<usize>,
+  shown: f64,
+) -> String {
+  let span = elapsed(ticks.end)
+    .zip(elapsed(ticks.start))
+    .map(|(end, start)| end - start);
+  elapsed(tick)
+    .zip(span)
+    .and_then(|(seconds, span)| axis.label(seconds, span))
+    .unwrap_or_else(|| tidy(shown))
+}
+
+/// The description of a time axis, falling back to the display time unit
+/// for numeric axes and times that don't convert to seconds.
+pub(super) fn time_description(
+  axis: TimeAxis,
+  in_seconds: bool,
+  unit: &Unit,
+) -> String {
+  axis
+    .description()
+    .filter(|_| in_seconds)
+    .map_or_else(|| unit.symbol.clone(), String::from)
+}
+
+/// What one of the charts of `SourceCharts` shows, by group index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartGroup {
+  Line(usize),
+  Distribution(usize),
+  Phase(usize),
+}
+
+/// The source and group of each chart of `SourceCharts`, in order: each
+/// source's line groups, then its distribution groups, then its phase
+/// groups.
+pub fn chart_groups<'a>(
+  sources: &[&'a dyn StatisticsSource],
+) -> Vec<(&'a dyn StatisticsSource, ChartGroup)> {
+  sources
+    .iter()
+    .flat_map(|source| {
+      let lines = (0..source.groups().len()).map(ChartGroup::Line);
+      let distributions =
+        (0..source.distribution_groups().len()).map(ChartGroup::Distribution);
+      let phases = (0..source.phase_groups().len()).map(ChartGroup::Phase);
+      lines
+        .chain(distributions)
+        .chain(phases)
+        .map(move |group| (*source, group))
+    })
+    .collect()
+}
+
+/// Charts of every group of several sources, whatever their types.
+pub struct SourceCharts<'a> {
+  sources: &'a [&'a dyn StatisticsSource],
+  tick_range: TickRange,
+  options: &'a [ChartOptions],
+  baseline: Option<&'a dyn StatisticsSource>,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a> SourceCharts<'a> {
+  pub fn new(sources: &'a [&'a dyn StatisticsSource]) -> Self {
+    Self {
+      sources,
+      tick_range: TickRange::All,
+      options: &[],
+      baseline: None,
+      plot_areas: None,
+    }
+  }
+
+  /// Overlays a saved run on the line charts, matching groups by title.
+  pub fn baseline(mut self, baseline: &'a dyn StatisticsSource) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+
+  /// Options for each chart, in the order of `chart_groups`. Missing charts
+  /// use defaults.
+  pub fn options(mut self, options: &'a [ChartOptions]) -> Self {
+    self.options = options;
+    self
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  /// Collects the plot area of each chart, in the same order as `options`,
+  /// while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+}
+
+impl<'a> Chart for SourceCharts<'a> {
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    drawing_area.fill(&plotters::prelude::BLACK)?;
+    let (xs, ys) = drawing_area.get_pixel_range();
+    let charts = chart_groups(self.sources);
+    let cells = drawing_area.split_evenly(grid(
+      xs.end - xs.start,
+      ys.end - ys.start,
+      charts.len(),
+    ));
+
+    if let Some(plot_areas) = self.plot_areas {
+      plot_areas.borrow_mut().clear();
+    }
+
+    for (i, (source, group)) in charts.into_iter().enumerate() {
+      let areas = self.plot_areas.map_or(0, |areas| areas.borrow().len());
+      let options = self.options.get(i).cloned().unwrap_or_default();
+      let tick_range = self.tick_range.clone();
+      match group {
+        ChartGroup::Line(group) => {
+          let mut chart = SourceChart::new(source, group)
+            .tick_range(tick_range)
+            .options(options);
+          if let Some(baseline) = self.baseline {
+            chart = chart.baseline(baseline);
+          }
+          if let Some(plot_areas) = self.plot_areas {
+            chart = chart.plot_areas(plot_areas);
+          }
+          chart.draw(&cells[i])?;
+        }
+        ChartGroup::Distribution(group) => {
+          let mut chart =
+            DistributionChart::new(source, group).tick_range(tick_range);
+          if let Some(view) = options.distribution_view {
+            chart = chart.view(view);
+          }
+          if let Some(plot_areas) = self.plot_areas {
+            chart = chart.plot_areas(plot_areas);
+          }
+          chart.draw(&cells[i])?;
+        }
+        ChartGroup::Phase(group) => {
+          let mut chart = PhaseChart::new(source, group).tick_range(tick_range);
+          if let Some(coloring) = options.phase_coloring {
+            chart = chart.coloring(coloring);
+          }
+          if let Some(plot_areas) = self.plot_areas {
+            chart = chart.plot_areas(plot_areas);
+          }
+          chart.draw(&cells[i])?;
+        }
+      }
+      // Charts with nothing to draw leave an empty area, so areas stay in
+      // chart order
+      if let Some(plot_areas) = self.plot_areas {
+        let mut plot_areas = plot_areas.borrow_mut();
+        if plot_areas.len() == areas {
+          plot_areas.push(PlotArea {
+            pixels: (0..0, 0..0),
+            ticks: 0..0,
+            values: 0.0..0.0,
+            x_values: None,
+            histogram: None,
+            legend: vec![],
+          });
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+pub struct StatsChart<'a, TState, TStatistics: Statistics<TState>> {
+  /// All-time extremes of each series in the group.
+  max_values: &'a [f64],
+  min_values: &'a [f64],
+  group: &'a StatisticsGroup<TState, TStatistics>,
+  stats: &'a [(usize, TStatistics)],
+  tick_range: TickRange,
+  options: ChartOptions,
+  annotations: &'a [Annotation],
+  baseline: Option<&'a dyn StatisticsSource>,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>>
+  StatsChart<'a, TState, TStatistics>
+{
+  pub fn new(
+    group: &'a StatisticsGroup<TState, TStatistics>,
+    stats: &'a [(usize, TStatistics)],
+    min_values: &'a [f64],
+    max_values: &'a [f64],
+  ) -> Self {
+    Self {
+      min_values,
+      max_values,
+      group,
+      stats,
+      tick_range: TickRange::All,
+      options: ChartOptions::default(),
+      annotations: &[],
+      baseline: None,
+      plot_areas: None,
+    }
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  pub fn options(mut self, options: ChartOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  pub fn annotations(mut self, annotations: &'a [Annotation]) -> Self {
+    self.annotations = annotations;
+    self
+  }
+
+  /// Overlays the matching group of a saved run, as `SourceChart::baseline`.
+  pub fn baseline(mut self, baseline: &'a dyn StatisticsSource) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+
+  /// Appends this chart's plot area to `plot_areas` while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>> Chart
+  for StatsChart<'a, TState, TStatistics>
+{
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let source = GroupSamples {
+      group: self.group,
+      descriptors: [GroupDescriptor::of(self.group)],
+      stats: self.stats,
+      min_values: self.min_values,
+      max_values: self.max_values,
+      annotations: self.annotations,
+    };
+    let derived = self
+      .group
+      .derived
+      .iter()
+      .map(|derived| derived.label.clone())
+      .zip(self.group.derived_values(self.stats))
+      .collect();
+    let mut chart = SourceChart::new(&source, 0)
+      .extra_series(derived)
+      .tick_range(self.tick_range.clone())
+      .options(self.options.clone());
+    if let Some(baseline) = self.baseline {
+      chart = chart.baseline(baseline);
+    }
+    if let Some(plot_areas) = self.plot_areas {
+      chart = chart.plot_areas(plot_areas);
+    }
+    chart.draw(drawing_area)
+  }
+}
+
+/// One group's samples, as a source for `SourceChart`.
+struct GroupSamples<'a, TState, TStatistics: Statistics<TState>> {
+  group: &'a StatisticsGroup<TState, TStatistics>,
+  descriptors: [GroupDescriptor; 1],
+  stats: &'a [(usize, TStatistics)],
+  min_values: &'a [f64],
+  max_values: &'a [f64],
+  annotations: &'a [Annotation],
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>> StatisticsSource
+  for GroupSamples<'a, TState, TStatistics>
+{
+  fn time_unit(&self) -> Unit {
+    TStatistics::get_time_unit()
+  }
+
+  fn display_time_unit(&self) -> Unit {
+    shown_time_unit::<TState, TStatistics>()
+  }
+
+  fn time_axis(&self) -> TimeAxis {
+    TStatistics::get_time_axis()
+  }
+
+  fn map_tick(&self, tick: usize) -> f64 {
+    TStatistics::map_tick_unit(tick)
+  }
+
+  fn groups(&self) -> &[GroupDescriptor] {
+    &self.descriptors
+  }
+
+  fn len(&self) -> usize {
+    self.stats.len()
+  }
+
+  fn tick(&self, sample: usize) -> usize {
+    self.stats[sample].0
+  }
+
+  fn value(&self, sample: usize, _group: usize, stat: usize) -> f64 {
+    self.stats[sample]
+      .1
+      .get_value(self.group.names[stat].clone())
+  }
+
+  fn extremes(&self, _group: usize, stat: usize) -> (f64, f64) {
+    (self.min_values[stat], self.max_values[stat])
+  }
+
+  fn annotations(&self) -> &[Annotation] {
+    self.annotations
+  }
+}
+
+/// A line chart of one group of any `StatisticsSource`, with the group's
+/// derived series.
+pub struct SourceChart<'a> {
+  source: &'a dyn StatisticsSource,
+  group: usize,
+  extra_series: Vec<(String, Vec<(usize, f64)>)>,
+  tick_range: TickRange,
+  options: ChartOptions,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a> SourceChart<'a> {
+  pub fn new(source: &'a dyn StatisticsSource, group: usize) -> Self {
+    Self {
+      source,
+      group,
+      extra_series: source.derived_series(group),
+      tick_range: TickRange::All,
+      options: ChartOptions::default(),
+      plot_areas: None,
+    }
+  }
+
+  /// Overlays the series of the group with the same title in `baseline`,
+  /// such as a saved run, after any other series.
+  pub fn baseline(mut self, baseline: &dyn StatisticsSource) -> Self {
+    let title = &self.descriptor().title;
+    let group = match baseline
+      .groups()
+      .iter()
+      .position(|group| &group.title == title)
+    {
+      Some(group) => group,
+      None => return self,
+    };
+    for (i, stat) in baseline.groups()[group].stats.iter().enumerate() {
+      let points = (0..baseline.len())
+        .map(|sample| (baseline.tick(sample), baseline.value(sample, group, i)))
+        .collect();
+      self
+        .extra_series
+        .push((format!("{} (baseline)", stat.label), points));
+    }
+    self
+  }
+
+  /// Labelled `(tick, value)` series drawn after the source's own, such as
+  /// the derived series of a group with no source of its own.
+  pub fn extra_series(
+    mut self,
+    series: Vec<(String, Vec<(usize, f64)>)>,
+  ) -> Self {
+    self.extra_series.extend(series);
+    self
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  pub fn options(mut self, options: ChartOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Appends this chart's plot area to `plot_areas` while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+
+  fn descriptor(&self) -> &'a GroupDescriptor {
+    &self.source.groups()[self.group]
+  }
+
+  /// The y range to plot, in plotted (possibly log) units.
+  fn value_range(&self, visible: Range<usize>) -> (f64, f64) {
+    let options = &self.options;
+    let samples = match options.autoscale {
+      Autoscale::History => 0..self.source.len(),
+      Autoscale::Window => visible,
+    };
+    let recorded_values = || {
+      samples.clone().flat_map(move |sample| {
+        self
+          .visible_series()
+          .map(move |i| self.plotted(self.source.value(sample, self.group, i)))
+      })
+    };
+    let window = self.tick_span(samples.clone());
+    let extra_values = || {
+      self
+        .visible_extra()
+        .flat_map(|(_, points)| points.iter())
+        .filter(|(tick, _)| window.contains(tick))
+        .map(move |(_, value)| self.plotted(*value))
+    };
+    let values = || recorded_values().chain(extra_values());
+
+    let range = if let Some(percentile) = options.clip_percentile {
+      let mut values: Vec<_> = values().filter(|v| v.is_finite()).collect();
+      values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+      if values.is_empty() {
+        None
+      } else {
+        let last = (values.len() - 1) as f64;
+        let percentile = percentile.clamp(0.0, 0.5);
+        Some((
+          values[(percentile * last).floor() as usize],
+          values[((1.0 - percentile) * last).ceil() as usize],
+        ))
+      }
+    } else if options.autoscale == Autoscale::History && !options.log_scale {
+      // The tracked extremes save scanning the whole history
+      let range = self.visible_series().fold(None, |range, i| {
+        let (min, max) = self.source.extremes(self.group, i);
+        extend_range(extend_range(range, min), max)
+      });
+      extra_values().fold(range, extend_range)
+    } else {
+      values().fold(None, extend_range)
+    };
+
+    let (mut min, mut max) = range.unwrap_or((0.0, 1.0));
+    if options.anchor_zero && !options.log_scale {
+      min = min.min(0.0);
+      max = max.max(0.0);
+    }
+    if max <= min {
+      // Give flat series some room rather than a zero height axis
+      let pad = if min == 0.0 { 1.0 } else { min.abs() * 0.05 };
+      min -= pad;
+      max += pad;
+    }
+    (min, max)
+  }
+
+  fn visible_series(&self) -> impl Iterator<Item = usize> + '_ {
+    (0..self.descriptor().stats.len())
+      .filter(move |i| !self.options.hidden.contains(i))
+  }
+
+  fn visible_extra(
+    &self,
+  ) -> impl Iterator<Item = (usize, &Vec<(usize, f64)>)> + '_ {
+    let offset = self.descriptor().stats.len();
+    self
+      .extra_series
+      .iter()
+      .enumerate()
+      .map(move |(k, (_, points))| (offset + k, points))
+      .filter(move |(i, _)| !self.options.hidden.contains(i))
+  }
+
+  /// The ticks from the first to the last of `samples`.
+  fn tick_span(&self, samples: Range<usize>) -> Range<usize> {
+    if samples.is_empty() {
+      return 0..0;
+    }
+    self.source.tick(samples.start)..self.source.tick(samples.end - 1) + 1
+  }
+
+  fn plotted(&self, value: f64) -> f64 {
+    if self.options.log_scale {
+      // Non-positive values have no place on a log axis
+      if value > 0.0 {
+        value.log10()
+      } else {
+        f64::NAN
+      }
+    } else {
+      value
+    }
+  }
+}
+
+impl<'a> Chart for SourceChart<'a> {
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let (xs, ys) = drawing_area.get_pixel_range();
+    let (_, h) = ((xs.end - xs.start) as f64, (ys.end - ys.start) as f64);
+
+    // Nothing has been recorded yet during warm-up
+    if self.source.is_empty() {
+      return Ok(());
+    }
+    let (first, last) =
+      (self.source.tick(0), self.source.tick(self.source.len() - 1));
+    let mut ticks = self.tick_range.resolve(first, last);
+    if ticks.end <= ticks.start {
+      ticks.end = ticks.start + 1;
+    }
+    let samples = visible_samples(self.source, &ticks);
+    let descriptor = self.descriptor();
+
+    let (min_value, max_value) = self.value_range(samples.clone());
+    let log_scale = self.options.log_scale;
+    // Values are plotted as recorded, and labelled in the display unit
+    let shown = |y: f64| {
+      descriptor.shown_value(if log_scale { 10f64.powf(y) } else { y })
+    };
+    let display = &descriptor.display_unit;
+    let axis_unit = display.axis_unit(shown(min_value), shown(max_value));
+    let label_font = ("sans-serif", h / 30.0)
+      .into_font()
+      .color(&plotters::prelude::WHITE);
+    let legend_centers = RefCell::new(vec![]);
+
+    {
+      let mut cc = ChartBuilder::on(drawing_area)
+        .margin(10)
+        .caption(
+          &descriptor.title,
+          ("sans-serif", h / 15.0)
+            .into_font()
+            .color(&plotters::prelude::WHITE),
+        )
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(
+          ticks.start as u32..ticks.end as u32,
+          min_value..max_value,
+        )?;
+
+      cc.configure_mesh()
+        .x_label_formatter(&|x| {
+          let tick = *x as usize;
+          time_label(
+            self.source.time_axis(),
+            &|tick| self.source.elapsed_seconds(tick),
+            tick,
+            &ticks,
+            self.source.shown_time(tick),
+          )
+        })
+        .y_label_formatter(&|y| {
+          let y = shown(*y);
+          axis_unit.axis_label(display.convert(y, &axis_unit).unwrap_or(y))
+        })
+        .x_labels(10)
+        .y_labels(10)
+        .x_desc(time_description(
+          self.source.time_axis(),
+          self.source.elapsed_seconds(0).is_some(),
+          &self.source.display_time_unit(),
+        ))
+        .y_desc(if log_scale {
+          format!("{} (log)", axis_unit)
+        } else {
+          axis_unit.symbol.clone()
+        })
+        .label_style(
+          ("sans-serif", h / 30.0)
+            .into_font()
+            .color(&plotters::prelude::WHITE),
+        )
+        .axis_style(plotters::prelude::WHITE.to_rgba())
+        .axis_desc_style(
+          ("sans-serif", h / 30.0)
+            .into_font()
+            .color(&plotters::prelude::WHITE),
+        )
+        .bold_line_style(plotters::prelude::WHITE.mix(0.5))
+        .light_line_style(plotters::prelude::WHITE.mix(0.25))
+        .draw()?;
+
+      draw_annotations(
+        &mut cc,
+        self.source.annotations(),
+        &ticks,
+        &(min_value..max_value),
+        h / 30.0,
+      )?;
+
+      let window = self.tick_span(samples.clone());
+      let recorded = (0..descriptor.stats.len()).map(|i| {
+        samples
+          .clone()
+          .map(|sample| {
+            (
+              self.source.tick(sample),
+              self.source.value(sample, self.group, i),
+            )
+          })
+          .collect::<Vec<_>>()
+      });
+      let extra = self.extra_series.iter().map(|(_, points)| {
+        points
+          .iter()
+          .filter(|(tick, _)| window.contains(tick))
+          .copied()
+          .collect()
+      });
+      let labels: Vec<_> = descriptor
+        .stats
+        .iter()
+        .map(|stat| &stat.label)
+        .chain(self.extra_series.iter().map(|(label, _)| label))
+        .collect();
+      for (i, (label, points)) in
+        labels.iter().zip(recorded.chain(extra)).enumerate()
+      {
+        let hidden = self.options.hidden.contains(&i);
+        let points: Vec<_> = if hidden {
+          vec![]
+        } else {
+          points
+            .into_iter()
+            .map(|(tick, value)| (tick as u32, self.plotted(value)))
+            .filter(|(_, value)| value.is_finite())
+            .collect()
+        };
+        let legend_centers = &legend_centers;
+        cc.draw_series(LineSeries::new(points, &Palette99::pick(i)))?
+          .label(label.as_str())
+          .legend(move |(x, y)| {
+            legend_centers.borrow_mut().push((i, (x, y)));
+            let color = Palette99::pick(i);
+            let style = if hidden { color.into() } else { color.filled() };
+            Rectangle::new([(x - 5, y - 5), (x + 5, y + 5)], style)
+          });
+      }
+
+      cc.configure_series_labels()
+        .background_style(plotters::prelude::BLACK.mix(0.8))
+        .border_style(plotters::prelude::WHITE)
+        .label_font(label_font.clone())
+        .position(SeriesLabelPosition::MiddleLeft)
+        .draw()?;
+
+      if let Some(plot_areas) = self.plot_areas {
+        let (pxs, pys) = cc.plotting_area().get_pixel_range();
+        let label_width = labels
+          .iter()
+          .map(|label| {
+            drawing_area
+              .estimate_text_size(label, &label_font)
+              .map_or(0, |(w, _)| w as i32)
+          })
+          .max()
+          .unwrap_or(0);
+        let row_height = (h / 30.0) as i32;
+        let legend = legend_centers
+          .borrow()
+          .iter()
+          .map(|&(i, (x, y))| {
+            let (x, y) = (pxs.start + x, pys.start + y);
+            (
+              i,
+              (
+                x - 10..x + 30 + label_width,
+                y - row_height / 2..y + row_height / 2 + 1,
+              ),
+            )
+          })
+          .collect();
+        plot_areas.borrow_mut().push(PlotArea {
+          pixels: (pxs, pys),
+          ticks: ticks.clone(),
+          values: min_value..max_value,
+          x_values: None,
+          histogram: None,
+          legend,
+        });
+      }
+    }
+
+    if self.options.highlight {
+      let (w, h) = drawing_area.dim_in_pixel();
+      drawing_area.draw(&Rectangle::new(
+        [(1, 1), (w as i32 - 2, h as i32 - 2)],
+        plotters::prelude::RED.stroke_width(3),
+      ))?;
+    }
+
+    Ok(())
+  }
+}
+
+fn extend_range(range: Option<(f64, f64)>, value: f64) -> Option<(f64, f64)> {
+  if !value.is_finite() {
+    return range;
+  }
+  match range {
+    None => Some((value, value)),
+    Some((min, max)) => Some((min.min(value), max.max(value))),
+  }
+}
+
+/// The samples of a source inside `ticks`, plus one either side so that
+/// lines run to the edge of the plot.
+pub(super) fn visible_samples(
+  source: &dyn StatisticsSource,
+  ticks: &Range<usize>,
+) -> Range<usize> {
+  let first_after = |edge: &dyn Fn(usize) -> bool| {
+    let (mut low, mut high) = (0, source.len());
+    while low < high {
+      let mid = (low + high) / 2;
+      if edge(source.tick(mid)) {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+    low
+  };
+  let start = first_after(&|tick| tick < ticks.start).saturating_sub(1);
+  let end = (first_after(&|tick| tick <= ticks.end) + 1).min(source.len());
+  start..end
+}
+
+/// The colour of annotation markers, apart from any series colour.
+pub(super) const ANNOTATION_COLOR: plotters::style::RGBColor =
+  plotters::style::RGBColor(255, 165, 0);
+
+/// Draws each annotation within `ticks`, including its last tick, as a
+/// vertical line, labelled at the top. Labels are staggered over a few rows
+/// so nearby events stay readable.
+pub(super) fn draw_annotations<DB: DrawingBackend>(
+  cc: &mut ChartContext<DB, Cartesian2d<RangedCoordu32, RangedCoordf64>>,
+  annotations: &[Annotation],
+  ticks: &Range<usize>,
+  values: &Range<f64>,
+  font_size: f64,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+  let visible = || {
+    annotations.iter().filter(move |annotation| {
+      (ticks.start..=ticks.end).contains(&annotation.tick)
+    })
+  };
+  cc.draw_series(visible().map(|annotation| {
+    let x = annotation.tick as u32;
+    PathElement::new(
+      vec![(x, values.start), (x, values.end)],
+      ANNOTATION_COLOR.mix(0.8),
+    )
+  }))?;
+  let font = ("sans-serif", font_size)
+    .into_font()
+    .color(&ANNOTATION_COLOR);
+  cc.draw_series(visible().enumerate().map(|(i, annotation)| {
+    let row = (i % 3) as i32 * font_size as i32;
+    EmptyElement::at((annotation.tick as u32, values.end))
+      + Text::new(annotation.label.clone(), (3, 2 + row), font.clone())
+  }))?;
+  Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::stats::{PhaseColoring, PhaseDescriptor, StatisticsSource};
+use std::{cell::RefCell, ops::Range};
+
+use plotters::{
+  coord::Shift,
+  prelude::{
+    ChartBuilder, Circle, DrawingArea, DrawingAreaErrorKind, DrawingBackend,
+    EmptyElement, Palette99, PathElement, Text, ViridisRGB,
+  },
+  style::{Color as PlottersColor, IntoFont, Palette},
+};
+
+use super::{
+  line_chart::{visible_samples, ANNOTATION_COLOR},
+  Chart, PlotArea, TickRange,
+};
+
+/// A chart of one phase group of any `StatisticsSource`.
+pub struct PhaseChart<'a> {
+  source: &'a dyn StatisticsSource,
+  group: usize,
+  coloring: PhaseColoring,
+  tick_range: TickRange,
+  plot_areas: Option<&'a RefCell<Vec<PlotArea>>>,
+}
+
+impl<'a> PhaseChart<'a> {
+  pub fn new(source: &'a dyn StatisticsSource, group: usize) -> Self {
+    Self {
+      source,
+      group,
+      coloring: source.phase_groups()[group].coloring,
+      tick_range: TickRange::All,
+      plot_areas: None,
+    }
+  }
+
+  pub fn coloring(mut self, coloring: PhaseColoring) -> Self {
+    self.coloring = coloring;
+    self
+  }
+
+  pub fn tick_range(mut self, tick_range: TickRange) -> Self {
+    self.tick_range = tick_range;
+    self
+  }
+
+  /// Appends this chart's plot area to `plot_areas` while drawing.
+  pub fn plot_areas(mut self, plot_areas: &'a RefCell<Vec<PlotArea>>) -> Self {
+    self.plot_areas = Some(plot_areas);
+    self
+  }
+}
+
+impl<'a> Chart for PhaseChart<'a> {
+  fn draw<DB: DrawingBackend>(
+    &self,
+    drawing_area: &DrawingArea<DB, Shift>,
+  ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let (_, ys) = drawing_area.get_pixel_range();
+    let h = (ys.end - ys.start) as f64;
+
+    let source = self.source;
+    if source.is_empty() {
+      return Ok(());
+    }
+    let (first, last) = (source.tick(0), source.tick(source.len() - 1));
+    let mut ticks = self.tick_range.resolve(first, last);
+    if ticks.end <= ticks.start {
+      ticks.end = ticks.start + 1;
+    }
+    let group: &PhaseDescriptor = &source.phase_groups()[self.group];
+    let mut points =
+      phase_points(source, self.group, visible_samples(source, &ticks));
+    if self.coloring == PhaseColoring::Trail {
+      points.drain(..points.len().saturating_sub(group.trail));
+    }
+
+    let x_range = padded_range(points.iter().map(|(_, x, _)| *x));
+    let y_range = padded_range(points.iter().map(|(_, _, y)| *y));
+
+    let mut cc = ChartBuilder::on(drawing_area)
+      .margin(10)
+      .caption(
+        &group.title,
+        ("sans-serif", h / 15.0)
+          .into_font()
+          .color(&plotters::prelude::WHITE),
+      )
+      .x_label_area_size(40)
+      .y_label_area_size(50)
+      .build_cartesian_2d(x_range.clone(), y_range.clone())?;
+
+    let label_style = ("sans-serif", h / 30.0)
+      .into_font()
+      .color(&plotters::prelude::WHITE);
+    cc.configure_mesh()
+      .x_labels(10)
+      .y_labels(10)
+      .x_desc(&group.x)
+      .y_desc(&group.y)
+      .label_style(label_style.clone())
+      .axis_desc_style(label_style)
+      .axis_style(plotters::prelude::WHITE.to_rgba())
+      .bold_line_style(plotters::prelude::WHITE.mix(0.5))
+      .light_line_style(plotters::prelude::WHITE.mix(0.25))
+      .draw()?;
+
+    // Position along the path in view, 0 at the oldest point and 1 at the
+    // newest
+    let age = |i: usize| i as f64 / (points.len() - 1).max(1) as f64;
+    let coloring = self.coloring;
+    cc.draw_series(points.windows(2).enumerate().map(|(i, pair)| {
+      let (_, x0, y0) = pair[0];
+      let (_, x1, y1) = pair[1];
+      let style = match coloring {
+        PhaseColoring::Gradient => ViridisRGB::get_color(age(i + 1)).filled(),
+        PhaseColoring::Trail => Palette99::pick(0).mix(age(i + 1)).filled(),
+      };
+      PathElement::new(vec![(x0, y0), (x1, y1)], style.stroke_width(2))
+    }))?;
+
+    let font = ("sans-serif", h / 30.0)
+      .into_font()
+      .color(&ANNOTATION_COLOR);
+    cc.draw_series(source.annotations().iter().filter_map(|annotation| {
+      // Skip events from before the oldest point, such as those cut from a
+      // trail
+      let (first, _, _) = points.first()?;
+      if annotation.tick < *first {
+        return None;
+      }
+      let i = points.partition_point(|(tick, _, _)| *tick < annotation.tick);
+      let (_, x, y) = points.get(i)?;
+      Some(
+        EmptyElement::at((*x, *y))
+          + Circle::new((0, 0), 3, ANNOTATION_COLOR.filled())
+          + Text::new(annotation.label.clone(), (5, -5), font.clone()),
+      )
+    }))?;
+
+    if let Some((_, x, y)) = points.last() {
+      cc.draw_series(std::iter::once(Circle::new(
+        (*x, *y),
+        4,
+        plotters::prelude::WHITE.filled(),
+      )))?;
+    }
+
+    if let Some(plot_areas) = self.plot_areas {
+      plot_areas.borrow_mut().push(PlotArea {
+        pixels: cc.plotting_area().get_pixel_range(),
+        ticks,
+        values: y_range,
+        x_values: Some(x_range),
+        histogram: None,
+        legend: vec![],
+      });
+    }
+
+    Ok(())
+  }
+}
+
+/// The `(tick, x, y)` of each of `samples` where both values are finite.
+pub(crate) fn phase_points(
+  source: &dyn StatisticsSource,
+  group: usize,
+  samples: Range<usize>,
+) -> Vec<(usize, f64, f64)> {
+  samples
+    .map(|sample| {
+      let (x, y) = source.phase_point(sample, group);
+      (source.tick(sample), x, y)
+    })
+    .filter(|(_, x, y)| x.is_finite() && y.is_finite())
+    .collect()
+}
+
+/// The range of `values` with a little room on each side.
+fn padded_range<I: Iterator<Item = f64>>(values: I) -> Range<f64> {
+  let (min, max) = values
+    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+      (min.min(v), max.max(v))
+    });
+  if !min.is_finite() || !max.is_finite() {
+    return 0.0..1.0;
+  }
+  let pad = if max > min {
+    (max - min) * 0.05
+  } else {
+    min.abs().max(1.0) * 0.05
+  };
+  min - pad..max + pad
+}
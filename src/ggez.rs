@@ -4,7 +4,9 @@ use ggez::{
 };
 
 pub mod app;
+mod chart_view;
 mod render;
+pub mod viewer;
 
 pub trait StateRenderer {
   type TAssets;
@@ -1,27 +1,36 @@
 use super::{
+  chart_view::ChartView,
   render::{
+    cache::ChartCache,
     layout::{Flex, FlexItem, Layout},
-    plotters::{icicle_chart::PerfChart, line_chart::StatsCharts},
     simulation::InternalStateRenderer,
   },
   StateRenderer,
 };
 use crate::{
+  charts::{chart_groups, Chart, ChartOptions, PerfChart},
   perf::{self, Perf},
-  stats::{Statistics, StatisticsTrackingSimulator},
+  stats::{
+    Alert, AlertAction, RecordedStats, Statistics, StatisticsSource,
+    StatisticsTrackingSimulator,
+  },
   Simulation,
 };
 use ggez::{
-  event::{EventHandler, MouseButton},
+  event::{self, EventHandler, KeyCode, KeyMods, MouseButton},
   graphics::{self, DrawParam, Rect},
   mint::Point2,
   timer, Context, GameError, GameResult,
 };
 use std::{
+  cell::RefCell,
   collections::VecDeque,
   time::{Duration, Instant},
 };
 
+/// The perf chart changes every frame, so only redraw it every few frames.
+const PERF_REFRESH_FRAMES: usize = 15;
+
 pub struct App<TSimulation, TStatistics>
 where
   TSimulation: Simulation,
@@ -36,6 +45,11 @@ where
   update_time: Option<Duration>,
   draw_time: Option<Duration>,
   tick_rate: f32,
+  paused: bool,
+  /// How many alerts of each panel's statistics the app has acted on.
+  alerts_seen: Vec<usize>,
+  /// Whether the app has paused for the simulator's stop reason.
+  stop_seen: bool,
   camera_position: [f32; 2],
   ticks: u32,
   simulator: StatisticsTrackingSimulator<TSimulation, TStatistics>,
@@ -43,6 +57,17 @@ where
   layout: Layout<AppSection>,
   perf: VecDeque<Perf>,
   ups: u32,
+  frames: usize,
+  /// The charts of the panel shown.
+  charts: ChartView,
+  /// The panel shown: the simulator's own statistics, then each attached
+  /// type.
+  panel: usize,
+  /// Options of the panels not shown, indexed by panel.
+  panel_options: Vec<Vec<ChartOptions>>,
+  /// A saved run overlaid on the simulation's line charts.
+  baseline: Option<RecordedStats>,
+  perf_cache: RefCell<ChartCache>,
 }
 
 impl<TSimulation, TStatistics> App<TSimulation, TStatistics>
@@ -56,16 +81,22 @@ where
     simulator: StatisticsTrackingSimulator<TSimulation, TStatistics>,
   ) -> Result<Self, ggez::GameError> {
     let drawable_size = graphics::drawable_size(ctx);
+    let panels = 1 + simulator.attached_panels().len();
+    let mouse_pos = ggez::input::mouse::position(ctx);
+    let charts = chart_groups(&[&simulator.stats]).len();
 
     Ok(Self {
       assets: simulator.state().load_assets(ctx)?,
-      mouse_pos: ggez::input::mouse::position(ctx),
+      mouse_pos,
       mouse_down: ggez::input::mouse::button_pressed(ctx, MouseButton::Left),
       drawable_size: [drawable_size.0, drawable_size.1],
       new_size: None,
       update_time: None,
       draw_time: None,
       tick_rate: 5000.0,
+      paused: false,
+      alerts_seen: vec![0; panels],
+      stop_seen: false,
       camera_position: [0.0, 0.0],
       ticks: 0,
       simulator,
@@ -107,8 +138,115 @@ where
       ]),
       perf: VecDeque::new(),
       ups: 0,
+      frames: 0,
+      charts: ChartView::new(charts, [mouse_pos.x, mouse_pos.y]),
+      panel: 0,
+      panel_options: vec![vec![]; panels],
+      baseline: None,
+      perf_cache: RefCell::new(ChartCache::default()),
     })
   }
+
+  /// Overlays a saved run, such as one loaded with `RecordedStats::open`, on
+  /// the line charts with the same titles.
+  pub fn baseline(mut self, baseline: RecordedStats) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+
+  fn screen(&self) -> Rect {
+    Rect {
+      x: 0.0,
+      y: 0.0,
+      w: self.drawable_size[0],
+      h: self.drawable_size[1],
+    }
+  }
+
+  /// Outlines a chart of a panel, shown or not.
+  fn highlight(&mut self, panel: usize, chart: usize) {
+    if self.panel == panel {
+      self.charts.options_mut(chart).highlight = true;
+      return;
+    }
+    // Attached panels start without options and use defaults
+    let options = &mut self.panel_options[panel];
+    if options.len() <= chart {
+      options.resize(chart + 1, ChartOptions::default());
+    }
+    options[chart].highlight = true;
+  }
+
+  /// Shows the next panel, keeping the options of the one hidden.
+  fn next_panel(&mut self) {
+    let panels = self.panel_options.len();
+    self
+      .charts
+      .swap_options(&mut self.panel_options[self.panel]);
+    self.panel = (self.panel + 1) % panels;
+    self
+      .charts
+      .swap_options(&mut self.panel_options[self.panel]);
+  }
+
+  /// Pauses or highlights charts for any alerts fired since the last call.
+  fn handle_alerts(&mut self) {
+    for panel in 0..self.alerts_seen.len() {
+      let alerts = self.panel_alerts(panel);
+      let fresh: Vec<_> = alerts[self.alerts_seen[panel]..]
+        .iter()
+        .map(|alert| (alert.action, alert.groups.clone()))
+        .collect();
+      self.alerts_seen[panel] += fresh.len();
+      for (action, groups) in fresh {
+        match action {
+          AlertAction::Pause | AlertAction::Stop => self.paused = true,
+          AlertAction::Highlight => {
+            for group in groups {
+              self.highlight(panel, group);
+            }
+          }
+          AlertAction::Log => {}
+        }
+      }
+    }
+    // Stop reasons of any statistics, such as convergence, pause the app once
+    if !self.stop_seen && self.simulator.stop_reason().is_some() {
+      self.stop_seen = true;
+      self.paused = true;
+    }
+  }
+
+  /// The alerts of a panel's statistics.
+  fn panel_alerts(&self, panel: usize) -> &[Alert] {
+    match panel {
+      0 => &self.simulator.stats.alerts,
+      _ => self.simulator.attached_panels()[panel - 1].alerts(),
+    }
+  }
+
+  /// The statistics of a panel, borrowing only the simulator so the charts
+  /// can be changed alongside.
+  fn panel_source(
+    simulator: &StatisticsTrackingSimulator<TSimulation, TStatistics>,
+    panel: usize,
+  ) -> &dyn StatisticsSource {
+    match panel {
+      0 => &simulator.stats,
+      panel => simulator.attached_panels()[panel - 1].source(),
+    }
+  }
+
+  /// Goes up with every sample or annotation the statistics of the panel
+  /// shown record, which only ever add to both.
+  fn panel_version(&self) -> usize {
+    let records = match self.panel {
+      0 => self.simulator.stats.records(),
+      panel => self.simulator.attached_panels()[panel - 1].records(),
+    };
+    let source = Self::panel_source(&self.simulator, self.panel);
+    records + source.annotations().len()
+  }
 }
 
 impl<TSimulation, TStatistics> EventHandler<GameError>
@@ -142,14 +280,21 @@ where
         .unwrap_or_else(|| Duration::new(0, 1));
 
       perf::span_of("Pacing Loop", || {
-        while self.ticks as f32 / timer::time_since_start(ctx).as_secs_f32()
-          < self.tick_rate
+        if self.paused {
+          // Don't race to catch up on the paused time when resuming
+          self.ticks = (timer::time_since_start(ctx).as_secs_f32()
+            * self.tick_rate) as u32;
+        }
+        while !self.paused
+          && self.ticks as f32 / timer::time_since_start(ctx).as_secs_f32()
+            < self.tick_rate
           && time_available.as_secs_f32() > 0.0
         {
           let tick_start = Instant::now();
           perf::span_of("Simulate", || {
             self.simulator.tick();
           });
+          self.handle_alerts();
           let tick_stop = Instant::now();
           let tick_duration = tick_stop - tick_start;
           self.ticks += 1;
@@ -179,7 +324,9 @@ where
     dy: f32,
   ) {
     self.mouse_pos = Point2::from([x, y]);
-    if self.mouse_down {
+    let sources = [Self::panel_source(&self.simulator, self.panel)];
+    self.charts.mouse_motion(&sources, x, y, dx);
+    if !self.charts.dragging() && self.mouse_down {
       self.camera_position[0] -= dx / 1.75 * self.zoom_level.powf(1.0 / 3.0);
       self.camera_position[1] -= dy / 1.75 * self.zoom_level.powf(1.0 / 3.0);
     }
@@ -193,7 +340,9 @@ where
     _y: f32,
   ) {
     if let MouseButton::Left = button {
-      self.mouse_down = true;
+      if !self.charts.mouse_down() {
+        self.mouse_down = true;
+      }
     }
   }
 
@@ -206,21 +355,19 @@ where
   ) {
     if let MouseButton::Left = button {
       self.mouse_down = false;
+      self.charts.mouse_up();
     }
   }
 
   fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+    let sources = [Self::panel_source(&self.simulator, self.panel)];
+    if self.charts.mouse_wheel(&sources, y) {
+      return;
+    }
+
     let sim_rect = self
       .layout
-      .get(
-        &AppSection::Simulation,
-        Rect {
-          x: 0.0,
-          y: 0.0,
-          w: self.drawable_size[0],
-          h: self.drawable_size[1],
-        },
-      )
+      .get(&AppSection::Simulation, self.screen())
       .unwrap();
 
     let d_zoom = 0.05 * y * self.zoom_level;
@@ -262,8 +409,15 @@ where
         &mut |section, bounds| match section {
           AppSection::None => Ok(()),
           AppSection::Perf => perf::span_of("Perf", || {
-            PerfChart::new(&self.perf.iter().collect::<Perf>().folded())
-              .draw(ctx, bounds)
+            self.perf_cache.borrow_mut().draw_with(
+              ctx,
+              bounds,
+              self.frames / PERF_REFRESH_FRAMES,
+              |buffer, size| {
+                let folded = self.perf.iter().collect::<Perf>().folded();
+                PerfChart::new(&folded).render_rgb(buffer, size)
+              },
+            )
           }),
           AppSection::Simulation => perf::span_of("Simulation", || {
             InternalStateRenderer::new(self.simulator.state(), &self.assets)
@@ -272,18 +426,46 @@ where
               .draw(ctx, bounds)
           }),
           AppSection::Stats => perf::span_of("Stats", || {
-            StatsCharts::new(&self.simulator.stats).draw(ctx, bounds)
+            // The baseline is a run of the simulation's own statistics
+            let baseline = match self.panel {
+              0 => self.baseline.as_ref().map(|baseline| baseline as _),
+              _ => None,
+            };
+            self.charts.draw(
+              ctx,
+              bounds,
+              &[Self::panel_source(&self.simulator, self.panel)],
+              baseline,
+              self.panel_version(),
+            )
           }),
           AppSection::Ups => perf::span_of("UPS", || {
+            let mut text = format!(
+              "UPS: {}",
+              self.ups as f64
+                / (self.update_time.unwrap_or_else(|| Duration::new(0, 0))
+                  + self.draw_time.unwrap_or_else(|| Duration::new(0, 0)))
+                .as_secs_f64()
+            );
+            if self.paused {
+              text.push_str("  Paused");
+            }
+            if self.panel_options.len() > 1 {
+              text.push_str(&format!(
+                "  Panel {}/{}",
+                self.panel + 1,
+                self.panel_options.len()
+              ));
+            }
+            let last_alert = (0..self.alerts_seen.len())
+              .filter_map(|panel| self.panel_alerts(panel).last())
+              .max_by_key(|alert| alert.tick);
+            if let Some(alert) = last_alert {
+              text.push_str(&format!("  Last alert at {}", alert));
+            }
             let ups_text = graphics::Text::new(
-              graphics::TextFragment::new(format!(
-                "UPS: {}",
-                self.ups as f64
-                  / (self.update_time.unwrap_or_else(|| Duration::new(0, 0))
-                    + self.draw_time.unwrap_or_else(|| Duration::new(0, 0)))
-                  .as_secs_f64()
-              ))
-              .scale(graphics::PxScale::from(bounds.h)),
+              graphics::TextFragment::new(text)
+                .scale(graphics::PxScale::from(bounds.h)),
             );
             graphics::draw(
               ctx,
@@ -296,12 +478,38 @@ where
 
       graphics::present(ctx)?;
 
+      self.frames += 1;
       self.draw_time = Some(Instant::now() - draw_start);
 
       Ok(())
     })
   }
 
+  fn key_down_event(
+    &mut self,
+    ctx: &mut Context,
+    keycode: KeyCode,
+    _keymods: KeyMods,
+    _repeat: bool,
+  ) {
+    match keycode {
+      KeyCode::Escape => event::quit(ctx),
+      KeyCode::Space => self.paused = !self.paused,
+      KeyCode::Tab => self.next_panel(),
+      // Acknowledge alerts on every panel, shown or not
+      KeyCode::H => {
+        self.charts.clear_highlights();
+        for options in self.panel_options.iter_mut().flatten() {
+          options.highlight = false;
+        }
+      }
+      keycode => {
+        let sources = [Self::panel_source(&self.simulator, self.panel)];
+        self.charts.key_down(&sources, keycode);
+      }
+    }
+  }
+
   fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) {
     self.new_size = Some((width, height));
   }
@@ -0,0 +1,277 @@
+use super::render::{cache::ChartCache, hover::StatsHover, Drawable};
+use crate::{
+  charts::{
+    chart_groups, ChartGroup, ChartOptions, PlotArea, SourceCharts, TickRange,
+  },
+  stats::StatisticsSource,
+};
+use ggez::{event::KeyCode, graphics::Rect, Context, GameResult};
+use std::cell::{Cell, RefCell};
+
+/// The charts of some statistics sources and their controls, shared by the
+/// app and the viewer. Dragging a chart pans the ticks shown and the wheel
+/// zooms them, clicking a legend entry toggles its series, and keys change
+/// the options of the chart under the mouse.
+pub(crate) struct ChartView {
+  tick_range: TickRange,
+  /// Options of each chart, in the order of `chart_groups`.
+  options: Vec<ChartOptions>,
+  /// Goes up whenever the tick range or options change.
+  version: usize,
+  plot_areas: RefCell<Vec<PlotArea>>,
+  /// Keyed by `version` and the version of the sources drawn.
+  cache: RefCell<ChartCache<(usize, usize)>>,
+  /// Where the charts were last drawn, to find the mouse on them.
+  bounds: Cell<Rect>,
+  mouse_pos: [f32; 2],
+  drag: bool,
+  pan_carry: f64,
+}
+
+impl ChartView {
+  pub fn new(charts: usize, mouse_pos: [f32; 2]) -> Self {
+    Self {
+      tick_range: TickRange::All,
+      options: vec![ChartOptions::default(); charts],
+      version: 0,
+      plot_areas: RefCell::new(vec![]),
+      cache: RefCell::new(ChartCache::default()),
+      bounds: Cell::new(Rect::default()),
+      mouse_pos,
+      drag: false,
+      pan_carry: 0.0,
+    }
+  }
+
+  /// Shows other sources, swapping in their chart options and forgetting
+  /// the charts drawn.
+  pub fn swap_options(&mut self, options: &mut Vec<ChartOptions>) {
+    std::mem::swap(&mut self.options, options);
+    self.plot_areas.borrow_mut().clear();
+    self.cache = RefCell::new(ChartCache::default());
+  }
+
+  pub fn dragging(&self) -> bool {
+    self.drag
+  }
+
+  /// Options of a chart. Charts past the end of `options` start from
+  /// defaults.
+  pub fn options_mut(&mut self, chart: usize) -> &mut ChartOptions {
+    self.version += 1;
+    if self.options.len() <= chart {
+      self.options.resize(chart + 1, ChartOptions::default());
+    }
+    &mut self.options[chart]
+  }
+
+  fn set_tick_range(&mut self, tick_range: TickRange) {
+    if self.tick_range != tick_range {
+      self.tick_range = tick_range;
+      self.version += 1;
+    }
+  }
+
+  /// Acknowledges alerts by clearing chart highlights.
+  pub fn clear_highlights(&mut self) {
+    self.version += 1;
+    for options in self.options.iter_mut() {
+      options.highlight = false;
+    }
+  }
+
+  /// The index and plot area of the chart under the mouse, and the mouse
+  /// position relative to the charts.
+  fn area_under_mouse(&self) -> Option<(usize, PlotArea, (i32, i32))> {
+    let bounds = self.bounds.get();
+    let local = (
+      (self.mouse_pos[0] - bounds.x) as i32,
+      (self.mouse_pos[1] - bounds.y) as i32,
+    );
+    self
+      .plot_areas
+      .borrow()
+      .iter()
+      .enumerate()
+      .find(|(_, area)| area.contains(local))
+      .map(|(i, area)| (i, area.clone(), local))
+  }
+
+  fn options_under_mouse(&mut self) -> Option<&mut ChartOptions> {
+    let (i, _, _) = self.area_under_mouse()?;
+    Some(self.options_mut(i))
+  }
+
+  /// Pans the charts while dragging.
+  pub fn mouse_motion(
+    &mut self,
+    sources: &[&dyn StatisticsSource],
+    x: f32,
+    y: f32,
+    dx: f32,
+  ) {
+    self.mouse_pos = [x, y];
+    if !self.drag {
+      return;
+    }
+    // Histograms show bins, not ticks
+    if let Some((_, area, _)) = self.area_under_mouse() {
+      if area.histogram.is_none() {
+        let (first, last) = recorded_ticks(sources);
+        let tick_range =
+          self
+            .tick_range
+            .panned(first, last, &area, dx, &mut self.pan_carry);
+        self.set_tick_range(tick_range);
+      }
+    }
+  }
+
+  /// Toggles the series of a legend entry under the mouse, or else starts
+  /// dragging the chart under it. Returns whether the mouse is on a chart.
+  pub fn mouse_down(&mut self) -> bool {
+    let (_, area, local) = match self.area_under_mouse() {
+      Some(found) => found,
+      None => return false,
+    };
+    match area.legend_entry_at(local) {
+      Some(series) => {
+        if let Some(options) = self.options_under_mouse() {
+          options.toggle_series(series);
+        }
+      }
+      None => {
+        self.drag = true;
+        self.pan_carry = 0.0;
+      }
+    }
+    true
+  }
+
+  pub fn mouse_up(&mut self) {
+    self.drag = false;
+  }
+
+  /// Zooms the ticks shown about the mouse. Returns whether the mouse is on
+  /// a chart.
+  pub fn mouse_wheel(
+    &mut self,
+    sources: &[&dyn StatisticsSource],
+    y: f32,
+  ) -> bool {
+    let (_, area, (mouse_x, _)) = match self.area_under_mouse() {
+      Some(found) => found,
+      None => return false,
+    };
+    if area.histogram.is_none() {
+      let (first, last) = recorded_ticks(sources);
+      let tick_range = self.tick_range.zoomed(first, last, &area, mouse_x, y);
+      self.set_tick_range(tick_range);
+    }
+    true
+  }
+
+  /// Handles the chart keys: F follows the most recent ticks and R shows
+  /// them all, while L, W, Z, P and V toggle the log scale, autoscale, zero
+  /// anchor, percentile clipping and view of the chart under the mouse.
+  pub fn key_down(
+    &mut self,
+    sources: &[&dyn StatisticsSource],
+    keycode: KeyCode,
+  ) {
+    match keycode {
+      // Keep the current window width
+      KeyCode::F => {
+        let (first, last) = recorded_ticks(sources);
+        let ticks = self.tick_range.resolve(first, last);
+        self
+          .set_tick_range(TickRange::Latest((ticks.end - ticks.start).max(2)));
+      }
+      KeyCode::R => self.set_tick_range(TickRange::All),
+      KeyCode::L => {
+        if let Some(options) = self.options_under_mouse() {
+          options.toggle_log_scale();
+        }
+      }
+      KeyCode::W => {
+        if let Some(options) = self.options_under_mouse() {
+          options.toggle_autoscale();
+        }
+      }
+      KeyCode::Z => {
+        if let Some(options) = self.options_under_mouse() {
+          options.toggle_anchor_zero();
+        }
+      }
+      KeyCode::P => {
+        if let Some(options) = self.options_under_mouse() {
+          options.toggle_clip_percentile(0.01);
+        }
+      }
+      KeyCode::V => {
+        let i = match self.area_under_mouse() {
+          Some((i, _, _)) => i,
+          None => return,
+        };
+        match chart_groups(sources).get(i) {
+          Some((source, ChartGroup::Distribution(group))) => {
+            let view = source.distribution_groups()[*group].view;
+            self.options_mut(i).cycle_distribution_view(view);
+          }
+          Some((source, ChartGroup::Phase(group))) => {
+            let coloring = source.phase_groups()[*group].coloring;
+            self.options_mut(i).cycle_phase_coloring(coloring);
+          }
+          _ => {}
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// Draws the charts of `sources`, then a readout of the chart under the
+  /// mouse. The charts are only re-rendered when the tick range or options
+  /// change, or `version`, which should go up whenever the sources change.
+  pub fn draw(
+    &self,
+    ctx: &mut Context,
+    at: Rect,
+    sources: &[&dyn StatisticsSource],
+    baseline: Option<&dyn StatisticsSource>,
+    version: usize,
+  ) -> GameResult<()> {
+    self.bounds.set(at);
+    self
+      .cache
+      .borrow_mut()
+      .draw(ctx, at, (self.version, version), || {
+        let charts = SourceCharts::new(sources)
+          .tick_range(self.tick_range.clone())
+          .options(&self.options)
+          .plot_areas(&self.plot_areas);
+        match baseline {
+          Some(baseline) => charts.baseline(baseline),
+          None => charts,
+        }
+      })?;
+    if !self.drag {
+      StatsHover::new(sources, &self.plot_areas.borrow(), self.mouse_pos)
+        .draw(ctx, at)?;
+    }
+    Ok(())
+  }
+}
+
+/// The first and last recorded ticks over every source.
+fn recorded_ticks(sources: &[&dyn StatisticsSource]) -> (usize, usize) {
+  let ticks = || {
+    sources
+      .iter()
+      .filter(|source| !source.is_empty())
+      .map(|source| (source.tick(0), source.tick(source.len() - 1)))
+  };
+  (
+    ticks().map(|(first, _)| first).min().unwrap_or(0),
+    ticks().map(|(_, last)| last).max().unwrap_or(0),
+  )
+}
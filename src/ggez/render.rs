@@ -1,3 +1,5 @@
+pub(crate) mod cache;
+pub(crate) mod hover;
 pub(crate) mod layout;
 pub(crate) mod plotters;
 pub(crate) mod simulation;
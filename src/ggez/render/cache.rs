@@ -0,0 +1,96 @@
+use ggez::{
+  graphics::{self, DrawParam, Image, Rect},
+  Context, GameError, GameResult,
+};
+
+use crate::charts::{Chart, ChartError};
+
+/// Keeps the last rendered image of a chart and only re-renders it when the
+/// version or the bounds change.
+pub struct ChartCache<K = usize> {
+  rgb: Vec<u8>,
+  rgba: Vec<u8>,
+  image: Option<Image>,
+  key: Option<(K, [u32; 2])>,
+}
+
+impl<K> Default for ChartCache<K> {
+  fn default() -> Self {
+    Self {
+      rgb: vec![],
+      rgba: vec![],
+      image: None,
+      key: None,
+    }
+  }
+}
+
+impl<K: PartialEq> ChartCache<K> {
+  pub fn draw<C, F>(
+    &mut self,
+    ctx: &mut Context,
+    at: Rect,
+    version: K,
+    chart: F,
+  ) -> GameResult<()>
+  where
+    C: Chart,
+    F: FnOnce() -> C,
+  {
+    self.draw_with(ctx, at, version, |buffer, size| {
+      chart().render_rgb(buffer, size)
+    })
+  }
+
+  /// Like `draw`, for charts rendered by a function into an RGB buffer.
+  pub fn draw_with<F>(
+    &mut self,
+    ctx: &mut Context,
+    at: Rect,
+    version: K,
+    render: F,
+  ) -> GameResult<()>
+  where
+    F: FnOnce(&mut [u8], (u32, u32)) -> Result<(), ChartError>,
+  {
+    let Rect { x, y, w, h } = at;
+    let size = [w as u32, h as u32];
+    if size[0] == 0 || size[1] == 0 {
+      return Ok(());
+    }
+
+    let key = Some((version, size));
+    if self.key != key || self.image.is_none() {
+      let pixels = size[0] as usize * size[1] as usize;
+      self.rgb.clear();
+      self.rgb.resize(pixels * 3 /* RGB */, 255);
+
+      render(&mut self.rgb, (size[0], size[1]))
+        .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+      rgb_to_rgba(&self.rgb, &mut self.rgba);
+      self.image = Some(Image::from_rgba8(
+        ctx,
+        size[0] as u16,
+        size[1] as u16,
+        &self.rgba,
+      )?);
+      self.key = key;
+    }
+
+    if let Some(image) = &self.image {
+      graphics::draw(ctx, image, DrawParam::default().dest([x, y]))?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Converts `rgb` into opaque RGBA, reusing the allocation held by `rgba`.
+pub fn rgb_to_rgba(rgb: &[u8], rgba: &mut Vec<u8>) {
+  rgba.resize(rgb.len() / 3 * 4, 255);
+  for (src, dst) in rgb.chunks_exact(3).zip(rgba.chunks_exact_mut(4)) {
+    dst[..3].copy_from_slice(src);
+    dst[3] = 255;
+  }
+}
@@ -0,0 +1,276 @@
+use crate::{
+  charts::{chart_groups, phase_chart::phase_points, ChartGroup, PlotArea},
+  ggez::render::Drawable,
+  stats::{DistributionDescriptor, Histogram, StatisticsSource},
+};
+use ggez::{
+  graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect, Text},
+  Context, GameResult,
+};
+
+/// Draws a tick marker and a readout of every series in the chart under the
+/// mouse, on top of already drawn `SourceCharts` of `sources`.
+pub struct StatsHover<'a> {
+  sources: &'a [&'a dyn StatisticsSource],
+  plot_areas: &'a [PlotArea],
+  mouse_pos: [f32; 2],
+}
+
+impl<'a> StatsHover<'a> {
+  pub fn new(
+    sources: &'a [&'a dyn StatisticsSource],
+    plot_areas: &'a [PlotArea],
+    mouse_pos: [f32; 2],
+  ) -> Self {
+    Self {
+      sources,
+      plot_areas,
+      mouse_pos,
+    }
+  }
+}
+
+impl<'a> Drawable for StatsHover<'a> {
+  fn draw(&self, ctx: &mut Context, at: Rect) -> GameResult<()> {
+    let local = (
+      (self.mouse_pos[0] - at.x) as i32,
+      (self.mouse_pos[1] - at.y) as i32,
+    );
+    let (chart, area) = match self
+      .plot_areas
+      .iter()
+      .enumerate()
+      .find(|(_, area)| area.contains(local))
+    {
+      Some(found) => found,
+      None => return Ok(()),
+    };
+    let (source, group) = match chart_groups(self.sources).get(chart) {
+      Some(found) => *found,
+      None => return Ok(()),
+    };
+
+    match (group, &area.histogram) {
+      (ChartGroup::Distribution(group), Some(histogram)) => {
+        let group = &source.distribution_groups()[group];
+        return self.draw_bin(ctx, at, local, group, area, histogram);
+      }
+      (ChartGroup::Phase(group), _) => {
+        return self.draw_phase(ctx, at, local, source, group, area)
+      }
+      _ => {}
+    }
+
+    if source.is_empty() {
+      return Ok(());
+    }
+    let tick = area.tick_at(local.0);
+    let (mut low, mut high) = (0, source.len());
+    while low < high {
+      let mid = (low + high) / 2;
+      if (source.tick(mid) as f64) < tick {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+    let nearest = [low.saturating_sub(1), low.min(source.len() - 1)]
+      .iter()
+      .copied()
+      .min_by(|a, b| {
+        let da = (source.tick(*a) as f64 - tick).abs();
+        let db = (source.tick(*b) as f64 - tick).abs();
+        da.partial_cmp(&db).unwrap()
+      })
+      .unwrap();
+    let tick = source.tick(nearest);
+
+    let x = at.x + area.x_of_tick(tick) as f32;
+    let (_, ys) = &area.pixels;
+    let line = Mesh::new_line(
+      ctx,
+      &[[x, at.y + ys.start as f32], [x, at.y + ys.end as f32]],
+      1.0,
+      Color::WHITE,
+    )?;
+    graphics::draw(ctx, &line, DrawParam::default())?;
+
+    let mut readout = format!(
+      "tick {}\n{} {}",
+      tick,
+      source.shown_time(tick),
+      source.display_time_unit()
+    );
+    if let Some(time) = source.time_readout(tick) {
+      readout.push_str(&format!("\n{}", time));
+    }
+    match group {
+      ChartGroup::Line(group) => {
+        let descriptor = &source.groups()[group];
+        let recorded = descriptor.stats.iter().enumerate().map(|(i, stat)| {
+          (stat.label.clone(), source.value(nearest, group, i))
+        });
+        let derived = source.derived_values(nearest, group);
+        for (label, value) in recorded.chain(derived) {
+          readout.push_str(&format!(
+            "\n{}: {:.3} {}",
+            label,
+            descriptor.shown_value(value),
+            descriptor.display_unit
+          ));
+        }
+      }
+      ChartGroup::Distribution(group) => {
+        let descriptor = &source.distribution_groups()[group];
+        let histogram = source.histogram(nearest, group);
+        for q in [0.05, 0.5, 0.95].iter() {
+          readout.push_str(&format!(
+            "\np{}: {:.3} {}",
+            q * 100.0,
+            descriptor.shown_value(histogram.quantile(*q)),
+            descriptor.display_unit
+          ));
+        }
+        readout.push_str(&format!("\ncount: {}", histogram.total()));
+      }
+      ChartGroup::Phase(_) => {}
+    }
+    self.draw_readout(ctx, at, x, readout)
+  }
+}
+
+impl<'a> StatsHover<'a> {
+  /// Marks the sample of a phase chart nearest the mouse.
+  fn draw_phase(
+    &self,
+    ctx: &mut Context,
+    at: Rect,
+    local: (i32, i32),
+    source: &dyn StatisticsSource,
+    group: usize,
+    area: &PlotArea,
+  ) -> GameResult<()> {
+    let descriptor = &source.phase_groups()[group];
+    let nearest = phase_points(source, group, 0..source.len())
+      .into_iter()
+      .filter(|(tick, _, _)| (area.ticks.start..=area.ticks.end).contains(tick))
+      .filter_map(|(tick, x, y)| {
+        let (px, py) = area.pixel_of(x, y)?;
+        let distance = (px - local.0).pow(2) + (py - local.1).pow(2);
+        Some((distance, tick, x, y, (px, py)))
+      })
+      .min_by_key(|(distance, ..)| *distance);
+    let (_, tick, x, y, (px, py)) = match nearest {
+      Some(nearest) => nearest,
+      None => return Ok(()),
+    };
+
+    let marker = Mesh::new_circle(
+      ctx,
+      DrawMode::stroke(1.0),
+      [at.x + px as f32, at.y + py as f32],
+      6.0,
+      0.5,
+      Color::WHITE,
+    )?;
+    graphics::draw(ctx, &marker, DrawParam::default())?;
+
+    let time = source
+      .time_readout(tick)
+      .map_or_else(String::new, |time| format!("\n{}", time));
+    let readout = format!(
+      "tick {}\n{} {}{}\n{}: {:.3}\n{}: {:.3}",
+      tick,
+      source.shown_time(tick),
+      source.display_time_unit(),
+      time,
+      descriptor.x,
+      x,
+      descriptor.y,
+      y
+    );
+    self.draw_readout(ctx, at, at.x + px as f32, readout)
+  }
+
+  /// Outlines the bin of a histogram view under the mouse.
+  fn draw_bin(
+    &self,
+    ctx: &mut Context,
+    at: Rect,
+    local: (i32, i32),
+    group: &DistributionDescriptor,
+    area: &PlotArea,
+    histogram: &Histogram,
+  ) -> GameResult<()> {
+    let value = match area.x_value_at(local.0) {
+      Some(value) => value,
+      None => return Ok(()),
+    };
+    let bin = histogram.bin_of(value);
+    let range = histogram.bin_range(bin);
+    let count = histogram.counts[bin];
+
+    let corners = area
+      .pixel_of(range.start, count)
+      .zip(area.pixel_of(range.end, 0.0));
+    if let Some(((x0, y0), (x1, y1))) = corners {
+      let outline = Mesh::new_rectangle(
+        ctx,
+        DrawMode::stroke(1.0),
+        Rect {
+          x: at.x + x0 as f32,
+          y: at.y + y0 as f32,
+          w: (x1 - x0) as f32,
+          h: (y1 - y0) as f32,
+        },
+        Color::WHITE,
+      )?;
+      graphics::draw(ctx, &outline, DrawParam::default())?;
+    }
+
+    let readout = format!(
+      "{:.3} to {:.3} {}\ncount: {}",
+      group.shown_value(range.start),
+      group.shown_value(range.end),
+      group.display_unit,
+      count
+    );
+    self.draw_readout(ctx, at, self.mouse_pos[0], readout)
+  }
+
+  /// Draws `readout` beside the marker at `x`, on a translucent background.
+  fn draw_readout(
+    &self,
+    ctx: &mut Context,
+    at: Rect,
+    x: f32,
+    readout: String,
+  ) -> GameResult<()> {
+    let text = Text::new(readout);
+    let dims = text.dimensions(ctx);
+
+    // Flip to the left of the marker when the readout would leave the chart
+    let padding = 4.0;
+    let mut text_x = x + padding * 2.0;
+    if text_x + dims.w + padding > at.x + at.w {
+      text_x = x - dims.w - padding * 2.0;
+    }
+    let text_y = self.mouse_pos[1];
+
+    let background = Mesh::new_rectangle(
+      ctx,
+      DrawMode::fill(),
+      Rect {
+        x: text_x - padding,
+        y: text_y - padding,
+        w: dims.w + padding * 2.0,
+        h: dims.h + padding * 2.0,
+      },
+      Color::new(0.0, 0.0, 0.0, 0.8),
+    )?;
+    graphics::draw(ctx, &background, DrawParam::default())?;
+    graphics::draw(ctx, &text, DrawParam::default().dest([text_x, text_y]))?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,136 @@
+use super::chart_view::ChartView;
+use crate::{
+  charts::chart_groups,
+  stats::{RecordedStats, StatisticsSource},
+};
+use ggez::{
+  event::{self, EventHandler, KeyCode, KeyMods, MouseButton},
+  graphics::{self, Rect},
+  Context, GameError, GameResult,
+};
+
+/// Shows the charts of recorded statistics, such as runs loaded with
+/// `RecordedStats::open`, with no live simulation.
+pub struct Viewer {
+  sources: Vec<RecordedStats>,
+  baseline: Option<RecordedStats>,
+  drawable_size: [f32; 2],
+  new_size: Option<(f32, f32)>,
+  charts: ChartView,
+}
+
+impl Viewer {
+  pub fn new(ctx: &mut Context, sources: Vec<RecordedStats>) -> Self {
+    let drawable_size = graphics::drawable_size(ctx);
+    let mouse_pos = ggez::input::mouse::position(ctx);
+    let charts = chart_groups(&as_sources(&sources)).len();
+    Self {
+      sources,
+      baseline: None,
+      drawable_size: [drawable_size.0, drawable_size.1],
+      new_size: None,
+      charts: ChartView::new(charts, [mouse_pos.x, mouse_pos.y]),
+    }
+  }
+
+  /// Overlays a saved run on the line charts with the same titles.
+  pub fn baseline(mut self, baseline: RecordedStats) -> Self {
+    self.baseline = Some(baseline);
+    self
+  }
+}
+
+impl EventHandler<GameError> for Viewer {
+  fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+    if let Some((w, h)) = self.new_size {
+      graphics::set_screen_coordinates(ctx, Rect { x: 0., y: 0., w, h })?;
+      self.drawable_size = [w, h];
+      self.new_size = None;
+    }
+    Ok(())
+  }
+
+  fn mouse_motion_event(
+    &mut self,
+    _ctx: &mut Context,
+    x: f32,
+    y: f32,
+    dx: f32,
+    _dy: f32,
+  ) {
+    self
+      .charts
+      .mouse_motion(&as_sources(&self.sources), x, y, dx);
+  }
+
+  fn mouse_button_down_event(
+    &mut self,
+    _ctx: &mut Context,
+    button: MouseButton,
+    _x: f32,
+    _y: f32,
+  ) {
+    if let MouseButton::Left = button {
+      self.charts.mouse_down();
+    }
+  }
+
+  fn mouse_button_up_event(
+    &mut self,
+    _ctx: &mut Context,
+    button: MouseButton,
+    _x: f32,
+    _y: f32,
+  ) {
+    if let MouseButton::Left = button {
+      self.charts.mouse_up();
+    }
+  }
+
+  fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+    self.charts.mouse_wheel(&as_sources(&self.sources), y);
+  }
+
+  fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+    graphics::clear(ctx, graphics::Color::BLACK);
+    let [w, h] = self.drawable_size;
+    // Recordings don't change, so the charts only redraw for new options
+    self.charts.draw(
+      ctx,
+      Rect {
+        x: 0.0,
+        y: 0.0,
+        w,
+        h,
+      },
+      &as_sources(&self.sources),
+      self.baseline.as_ref().map(|baseline| baseline as _),
+      0,
+    )?;
+    graphics::present(ctx)
+  }
+
+  fn key_down_event(
+    &mut self,
+    ctx: &mut Context,
+    keycode: KeyCode,
+    _keymods: KeyMods,
+    _repeat: bool,
+  ) {
+    match keycode {
+      KeyCode::Escape => event::quit(ctx),
+      keycode => self.charts.key_down(&as_sources(&self.sources), keycode),
+    }
+  }
+
+  fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) {
+    self.new_size = Some((width, height));
+  }
+}
+
+fn as_sources(recorded: &[RecordedStats]) -> Vec<&dyn StatisticsSource> {
+  recorded
+    .iter()
+    .map(|source| source as &dyn StatisticsSource)
+    .collect()
+}
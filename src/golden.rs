@@ -0,0 +1,637 @@
+use crate::{
+  stats::{
+    hypothesis::kolmogorov_smirnov, write_recording, GroupDescriptor,
+    RecordedStats, Statistics, StatisticsSource, StatisticsTrackingSimulator,
+    TestResult,
+  },
+  Simulation,
+};
+use std::{
+  fmt::Display,
+  fs::File,
+  io::{BufReader, BufWriter},
+  path::{Path, PathBuf},
+};
+
+/// Setting this environment variable makes golden tests rewrite their golden
+/// files instead of checking against them.
+pub const REGENERATE_VAR: &str = "REGENERATE_GOLDEN";
+
+/// How far a value may stray from its golden value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+  /// Within `absolute + relative * |golden|` of the golden value, run by run.
+  Within { absolute: f64, relative: f64 },
+  /// For ensembles: the mean over runs is within `sigmas` standard errors of
+  /// the golden mean, with the error combined from both ensembles.
+  Sigmas(f64),
+  /// For ensembles: a two-sample Kolmogorov-Smirnov test does not tell the
+  /// runs from the golden runs at level `alpha`, split evenly over the
+  /// series' samples. Catches changes in spread that leave the mean alone.
+  Distribution { alpha: f64 },
+}
+
+impl Tolerance {
+  pub fn exact() -> Self {
+    Tolerance::Within {
+      absolute: 0.0,
+      relative: 0.0,
+    }
+  }
+
+  pub fn absolute(absolute: f64) -> Self {
+    Tolerance::Within {
+      absolute,
+      relative: 0.0,
+    }
+  }
+
+  pub fn relative(relative: f64) -> Self {
+    Tolerance::Within {
+      absolute: 0.0,
+      relative,
+    }
+  }
+}
+
+impl Default for Tolerance {
+  fn default() -> Self {
+    Tolerance::Within {
+      absolute: 1e-9,
+      relative: 1e-9,
+    }
+  }
+}
+
+/// Runs a simulation headlessly with fixed seeds and compares its recorded
+/// statistics with a golden file.
+pub struct GoldenTest {
+  path: PathBuf,
+  ticks: usize,
+  seeds: Vec<u64>,
+  tolerance: Tolerance,
+  series_tolerances: Vec<(String, Tolerance)>,
+  regenerate: bool,
+}
+
+impl GoldenTest {
+  /// A test of `ticks` ticks with seed 0. Regenerates the golden file when
+  /// `REGENERATE_GOLDEN` is set.
+  pub fn new<P: AsRef<Path>>(path: P, ticks: usize) -> Self {
+    Self {
+      path: path.as_ref().into(),
+      ticks,
+      seeds: vec![0],
+      tolerance: Tolerance::default(),
+      series_tolerances: vec![],
+      regenerate: std::env::var_os(REGENERATE_VAR).is_some(),
+    }
+  }
+
+  /// Runs an ensemble, one run per seed.
+  pub fn seeds(mut self, seeds: impl IntoIterator<Item = u64>) -> Self {
+    self.seeds = seeds.into_iter().collect();
+    self
+  }
+
+  pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+    self.tolerance = tolerance;
+    self
+  }
+
+  /// Overrides the tolerance of one series, labelled `group/stat`.
+  pub fn series_tolerance(
+    mut self,
+    series: &str,
+    tolerance: Tolerance,
+  ) -> Self {
+    self.series_tolerances.push((series.into(), tolerance));
+    self
+  }
+
+  pub fn regenerate(mut self, regenerate: bool) -> Self {
+    self.regenerate = regenerate;
+    self
+  }
+
+  /// Runs the simulation built by `simulator` for each seed, then checks the
+  /// runs against the golden file or, when regenerating, rewrites it.
+  pub fn run<TSimulation, TStatistics, F>(
+    &self,
+    mut simulator: F,
+  ) -> Result<(), GoldenError>
+  where
+    TSimulation: Simulation,
+    TStatistics: Statistics<TSimulation::TState>,
+    F: FnMut(u64) -> StatisticsTrackingSimulator<TSimulation, TStatistics>,
+  {
+    let runs: Vec<_> = self
+      .seeds
+      .iter()
+      .map(|seed| {
+        let mut simulator = simulator(*seed);
+        simulator.run(self.ticks);
+        RecordedStats::from_source(&simulator.stats)
+      })
+      .collect();
+
+    if self.regenerate {
+      return self.write(&runs);
+    }
+    if !self.path.exists() {
+      return Err(GoldenError::Missing(self.path.clone()));
+    }
+    let file = File::open(&self.path).map_err(GoldenError::Io)?;
+    let golden =
+      RecordedStats::read_all(BufReader::new(file)).map_err(GoldenError::Io)?;
+    let diff = self.compare(&golden, &runs);
+    if diff.is_empty() {
+      Ok(())
+    } else {
+      Err(GoldenError::Mismatch(diff))
+    }
+  }
+
+  /// As `run`, panicking with the diff on failure, for use in tests.
+  pub fn check<TSimulation, TStatistics, F>(&self, simulator: F)
+  where
+    TSimulation: Simulation,
+    TStatistics: Statistics<TSimulation::TState>,
+    F: FnMut(u64) -> StatisticsTrackingSimulator<TSimulation, TStatistics>,
+  {
+    if let Err(e) = self.run(simulator) {
+      panic!("{}", e);
+    }
+  }
+
+  fn write(&self, runs: &[RecordedStats]) -> Result<(), GoldenError> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent).map_err(GoldenError::Io)?;
+    }
+    let mut writer =
+      BufWriter::new(File::create(&self.path).map_err(GoldenError::Io)?);
+    for run in runs {
+      write_recording(run, &mut writer).map_err(GoldenError::Io)?;
+    }
+    Ok(())
+  }
+
+  fn tolerance_of(&self, series: &str) -> Tolerance {
+    self
+      .series_tolerances
+      .iter()
+      .find(|(label, _)| label == series)
+      .map_or(self.tolerance, |(_, tolerance)| *tolerance)
+  }
+
+  fn compare(
+    &self,
+    golden: &[RecordedStats],
+    runs: &[RecordedStats],
+  ) -> GoldenDiff {
+    let mut diff = GoldenDiff::default();
+    if golden.len() != runs.len() {
+      diff.structure.push(format!(
+        "{} golden runs, {} runs now",
+        golden.len(),
+        runs.len()
+      ));
+      return diff;
+    }
+    let (expected, actual) = match (golden.first(), runs.first()) {
+      (Some(expected), Some(actual)) => (expected, actual),
+      _ => return diff,
+    };
+    if !same_series(&expected.groups, &actual.groups) {
+      diff.structure.push(format!(
+        "series differ: golden has {}, now {}",
+        series_labels(expected).join(", "),
+        series_labels(actual).join(", ")
+      ));
+      return diff;
+    }
+    for (g, run) in golden.iter().zip(runs).enumerate() {
+      let ticks = |recorded: &RecordedStats| -> Vec<usize> {
+        recorded.samples.iter().map(|(tick, _)| *tick).collect()
+      };
+      if ticks(run.0) != ticks(run.1) {
+        diff.structure.push(format!(
+          "run {}: recorded {} samples, golden has {}",
+          g,
+          run.1.samples.len(),
+          run.0.samples.len()
+        ));
+      }
+    }
+    if !diff.structure.is_empty() {
+      return diff;
+    }
+
+    for (g, group) in expected.groups.iter().enumerate() {
+      for (s, stat) in group.stats.iter().enumerate() {
+        let label = format!("{}/{}", group.title, stat.label);
+        let tolerance = self.tolerance_of(&label);
+        let compared = match tolerance {
+          Tolerance::Within { .. } => golden.len(),
+          Tolerance::Sigmas(_) | Tolerance::Distribution { .. } => 1,
+        };
+        let mut series = SeriesDiff {
+          label,
+          samples: expected.samples.len() * compared,
+          mismatches: vec![],
+        };
+        let samples = expected.samples.len();
+        for sample in 0..samples {
+          let tick = expected.samples[sample].0;
+          let values = |runs: &[RecordedStats]| -> Vec<f64> {
+            runs.iter().map(|run| run.samples[sample].1[g][s]).collect()
+          };
+          let (expected, actual) = (values(golden), values(runs));
+          match tolerance {
+            Tolerance::Within { absolute, relative } => {
+              for (run, (e, a)) in expected.iter().zip(actual).enumerate() {
+                let allowed = absolute + relative * e.abs();
+                if !close(*e, a, allowed) {
+                  series.mismatches.push(Mismatch {
+                    run: Some(run).filter(|_| golden.len() > 1),
+                    tick,
+                    golden: *e,
+                    actual: a,
+                    allowed,
+                    test: None,
+                  });
+                }
+              }
+            }
+            Tolerance::Sigmas(sigmas) => {
+              let (e, e_error) = mean_and_error(&expected);
+              let (a, a_error) = mean_and_error(&actual);
+              let allowed = sigmas * e_error.hypot(a_error);
+              if !close(e, a, allowed) {
+                series.mismatches.push(Mismatch {
+                  run: None,
+                  tick,
+                  golden: e,
+                  actual: a,
+                  allowed,
+                  test: None,
+                });
+              }
+            }
+            Tolerance::Distribution { alpha } => {
+              let alpha = alpha / samples as f64;
+              let finite = |values: &[f64]| -> Vec<f64> {
+                values.iter().copied().filter(|v| v.is_finite()).collect()
+              };
+              let test =
+                kolmogorov_smirnov(&finite(&expected), &finite(&actual));
+              if test.significant(alpha) {
+                series.mismatches.push(Mismatch {
+                  run: None,
+                  tick,
+                  golden: mean_and_error(&expected).0,
+                  actual: mean_and_error(&actual).0,
+                  allowed: alpha,
+                  test: Some(test),
+                });
+              }
+            }
+          }
+        }
+        if !series.mismatches.is_empty() {
+          diff.series.push(series);
+        }
+      }
+    }
+    diff
+  }
+}
+
+/// Whether two values agree to within `allowed`, with NaNs agreeing only
+/// with each other.
+fn close(expected: f64, actual: f64, allowed: f64) -> bool {
+  if expected.is_nan() || actual.is_nan() {
+    return expected.is_nan() && actual.is_nan();
+  }
+  expected == actual || (expected - actual).abs() <= allowed
+}
+
+/// The mean of the finite values and its standard error.
+fn mean_and_error(values: &[f64]) -> (f64, f64) {
+  let values: Vec<_> = values.iter().filter(|v| v.is_finite()).collect();
+  let n = values.len() as f64;
+  if values.is_empty() {
+    return (f64::NAN, 0.0);
+  }
+  let mean = values.iter().copied().sum::<f64>() / n;
+  if values.len() < 2 {
+    return (mean, 0.0);
+  }
+  let variance =
+    values.iter().map(|v| (*v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+  (mean, (variance / n).sqrt())
+}
+
+/// Whether the runs recorded the same series in the same units. Display
+/// units are left out, as they only change how values are shown.
+fn same_series(
+  expected: &[GroupDescriptor],
+  actual: &[GroupDescriptor],
+) -> bool {
+  expected.len() == actual.len()
+    && expected.iter().zip(actual).all(|(e, a)| {
+      e.title == a.title && e.unit == a.unit && e.stats == a.stats
+    })
+}
+
+fn series_labels(recorded: &RecordedStats) -> Vec<String> {
+  recorded
+    .groups()
+    .iter()
+    .flat_map(|group| {
+      group
+        .stats
+        .iter()
+        .map(move |stat| format!("{}/{}", group.title, stat.label))
+    })
+    .collect()
+}
+
+/// A sample that strayed too far from its golden value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+  /// The run of an ensemble, or `None` for a single run or ensemble means.
+  pub run: Option<usize>,
+  pub tick: usize,
+  pub golden: f64,
+  pub actual: f64,
+  /// The allowed difference, or for distributional tolerances the level
+  /// the test's p-value fell below.
+  pub allowed: f64,
+  /// The Kolmogorov-Smirnov test, for distributional tolerances, whose
+  /// `golden` and `actual` are ensemble means.
+  pub test: Option<TestResult>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesDiff {
+  /// The series, labelled `group/stat`.
+  pub label: String,
+  /// How many values were compared, counting each run's samples apart
+  /// unless ensemble means were compared.
+  pub samples: usize,
+  pub mismatches: Vec<Mismatch>,
+}
+
+/// How the runs differ from the golden file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoldenDiff {
+  /// Differences in shape, such as renamed series or missing samples, that
+  /// stop values being compared.
+  pub structure: Vec<String>,
+  pub series: Vec<SeriesDiff>,
+}
+
+impl GoldenDiff {
+  pub fn is_empty(&self) -> bool {
+    self.structure.is_empty() && self.series.is_empty()
+  }
+}
+
+/// How many mismatches of each series a diff lists.
+const SHOWN_MISMATCHES: usize = 5;
+
+impl Display for GoldenDiff {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for difference in self.structure.iter() {
+      writeln!(f, "{}", difference)?;
+    }
+    for series in self.series.iter() {
+      writeln!(
+        f,
+        "{}: {} of {} samples differ",
+        series.label,
+        series.mismatches.len(),
+        series.samples
+      )?;
+      for mismatch in series.mismatches.iter().take(SHOWN_MISMATCHES) {
+        write!(f, "  ")?;
+        if let Some(run) = mismatch.run {
+          write!(f, "run {}, ", run)?;
+        }
+        match mismatch.test {
+          Some(test) => writeln!(
+            f,
+            "tick {}: distributions differ, KS D {:.3}, p {:.3e} < {:.3e} \
+             (means: golden {}, actual {})",
+            mismatch.tick,
+            test.statistic,
+            test.p_value,
+            mismatch.allowed,
+            mismatch.golden,
+            mismatch.actual
+          )?,
+          None => writeln!(
+            f,
+            "tick {}: golden {}, actual {} (allowed ±{:.3e})",
+            mismatch.tick, mismatch.golden, mismatch.actual, mismatch.allowed
+          )?,
+        }
+      }
+      if series.mismatches.len() > SHOWN_MISMATCHES {
+        writeln!(
+          f,
+          "  and {} more",
+          series.mismatches.len() - SHOWN_MISMATCHES
+        )?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+  Io(std::io::Error),
+  /// There is no golden file yet.
+  Missing(PathBuf),
+  Mismatch(GoldenDiff),
+}
+
+impl Display for GoldenError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      GoldenError::Io(e) => e.fmt(f),
+      GoldenError::Missing(path) => write!(
+        f,
+        "No golden file at {}. Set {} to create it.",
+        path.display(),
+        REGENERATE_VAR
+      ),
+      GoldenError::Mismatch(diff) => {
+        writeln!(f, "Statistics differ from the golden file:")?;
+        diff.fmt(f)?;
+        write!(f, "Set {} to accept them.", REGENERATE_VAR)
+      }
+    }
+  }
+}
+
+impl std::error::Error for GoldenError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::stats::{StatisticsGroup, Unit};
+
+  /// Draws a fresh uniform value in `[-spread, spread]` about `mean` each
+  /// tick.
+  struct Noise {
+    mean: f64,
+    spread: f64,
+  }
+
+  struct State {
+    rng: u64,
+    value: f64,
+  }
+
+  impl Simulation for Noise {
+    type TState = State;
+
+    fn tick(&mut self, state: &mut State) {
+      state.rng ^= state.rng << 13;
+      state.rng ^= state.rng >> 7;
+      state.rng ^= state.rng << 17;
+      let uniform = (state.rng >> 11) as f64 / (1u64 << 53) as f64;
+      state.value = self.mean + self.spread * (2.0 * uniform - 1.0);
+    }
+  }
+
+  struct Stats {
+    value: f64,
+  }
+
+  impl Statistics<State> for Stats {
+    type TStatID = &'static str;
+
+    fn get_tick_unit() -> String {
+      "tick".into()
+    }
+
+    fn map_tick_unit(tick: usize) -> f64 {
+      tick as f64
+    }
+
+    fn get_groups() -> Vec<StatisticsGroup<State, Self>> {
+      vec![StatisticsGroup::new("Values", "m", vec!["value"])]
+    }
+
+    fn get_value(&self, _name: &'static str) -> f64 {
+      self.value
+    }
+
+    fn derive(state: &State) -> Self {
+      Self { value: state.value }
+    }
+  }
+
+  fn noise(
+    mean: f64,
+    spread: f64,
+  ) -> impl FnMut(u64) -> StatisticsTrackingSimulator<Noise, Stats> {
+    move |seed| {
+      // SplitMix64, so neighbouring seeds start far apart.
+      let mut rng = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+      rng = (rng ^ (rng >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+      rng = (rng ^ (rng >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+      StatisticsTrackingSimulator::new(
+        Noise { mean, spread },
+        State {
+          rng: rng ^ (rng >> 31),
+          value: mean,
+        },
+      )
+    }
+  }
+
+  fn golden_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+      .join(format!("simulate-golden-{}", std::process::id()))
+      .join(name)
+  }
+
+  #[test]
+  fn regenerates_passes_then_fails_with_diff() {
+    let path = golden_path("single.stats");
+    let test = GoldenTest::new(&path, 20).regenerate(false);
+    assert!(matches!(
+      test.run(noise(1.0, 1.0)),
+      Err(GoldenError::Missing(_))
+    ));
+
+    let regenerate = GoldenTest::new(&path, 20).regenerate(true);
+    regenerate.run(noise(1.0, 1.0)).unwrap();
+    test.run(noise(1.0, 1.0)).unwrap();
+
+    let diff = match test.run(noise(1.5, 1.0)) {
+      Err(GoldenError::Mismatch(diff)) => diff,
+      other => panic!("expected a mismatch, got {:?}", other),
+    };
+    assert!(diff.structure.is_empty());
+    assert_eq!(diff.series.len(), 1);
+    assert_eq!(diff.series[0].label, "Values/value");
+    assert_eq!(diff.series[0].samples, 21);
+    let first = &diff.series[0].mismatches[0];
+    assert!((first.actual - first.golden - 0.5).abs() < 1e-12);
+
+    let message = GoldenError::Mismatch(diff).to_string();
+    assert!(message.contains("Values/value: 21 of 21 samples differ"));
+    assert!(message.contains("tick 0: golden 1, actual 1.5"));
+    assert!(message.contains("and 16 more"));
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn distribution_catches_changed_spread() {
+    let path = golden_path("ensemble.stats");
+    let test = |tolerance, regenerate| {
+      GoldenTest::new(&path, 10)
+        .seeds(0..40)
+        .tolerance(tolerance)
+        .regenerate(regenerate)
+    };
+    let distribution = Tolerance::Distribution { alpha: 0.01 };
+    test(distribution, true).run(noise(0.0, 1.0)).unwrap();
+    test(distribution, false).run(noise(0.0, 1.0)).unwrap();
+    test(Tolerance::Sigmas(3.0), false)
+      .run(noise(0.0, 10.0))
+      .unwrap();
+
+    let diff = match test(distribution, false).run(noise(0.0, 10.0)) {
+      Err(GoldenError::Mismatch(diff)) => diff,
+      other => panic!("expected a mismatch, got {:?}", other),
+    };
+    assert_eq!(diff.series[0].samples, 11);
+    assert!(diff.series[0].mismatches.iter().all(|m| m.test.is_some()));
+    assert!(diff.to_string().contains("distributions differ, KS D"));
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn display_units_do_not_change_structure() {
+    let group = GroupDescriptor {
+      title: "Energy".into(),
+      unit: Unit::new("J"),
+      display_unit: Unit::new("J"),
+      stats: vec![],
+    };
+    let mut shown = group.clone();
+    shown.display_unit = Unit::new("kWh");
+    assert!(same_series(std::slice::from_ref(&group), &[shown]));
+
+    let mut converted = group.clone();
+    converted.unit = Unit::new("kWh");
+    assert!(!same_series(&[group], &[converted]));
+  }
+}
@@ -1,14 +1,29 @@
+pub mod golden;
 pub mod stats;
 
+use stats::ChangeEvents;
+
+#[cfg(feature = "charts")]
+pub mod charts;
+
 #[cfg(feature = "ggez_app")]
 pub mod ggez;
 
 #[cfg(feature = "perf")]
 pub mod perf;
+
+#[cfg(feature = "charts")]
+pub mod report;
 pub trait Simulation {
   type TState;
 
   fn tick(&mut self, state: &mut Self::TState);
+
+  /// Labels of notable events in the last tick. Statistics trackers record
+  /// them as annotations.
+  fn annotations(&mut self) -> Vec<String> {
+    vec![]
+  }
 }
 
 pub struct Simulator<TSimulation>
@@ -37,4 +52,15 @@ where
   pub fn state(&self) -> &TSimulation::TState {
     &self.state
   }
+
+  pub fn annotations(&mut self) -> Vec<String> {
+    self.simulation.annotations()
+  }
+
+  pub fn changes<TChange>(&mut self) -> Vec<TChange>
+  where
+    TSimulation: ChangeEvents<TChange>,
+  {
+    self.simulation.changes()
+  }
 }
@@ -0,0 +1,377 @@
+use crate::{
+  charts::{
+    Chart, ChartError, ChartOptions, DistributionChart, PhaseChart, StatsChart,
+  },
+  stats::{AlertAction, SimStats, Statistics, StatisticsSource, TimeAxis},
+};
+use std::{
+  fmt::{Display, Write as _},
+  path::Path,
+  time::Duration,
+};
+
+#[cfg(feature = "perf")]
+use crate::{
+  charts::{icicle_chart::HumanReadableDuration, PerfChart},
+  perf::FoldedSpans,
+};
+
+const CHART_SIZE: (u32, u32) = (800, 450);
+
+/// A self-contained HTML summary of a finished run.
+pub struct Report<'a, TState, TStatistics: Statistics<TState>> {
+  title: String,
+  stats: &'a SimStats<TState, TStatistics>,
+  seed: Option<u64>,
+  ticks: Option<usize>,
+  wall_time: Option<Duration>,
+  parameters: Vec<(String, String)>,
+  #[cfg(feature = "perf")]
+  perf: Option<&'a FoldedSpans>,
+}
+
+impl<'a, TState, TStatistics: Statistics<TState>>
+  Report<'a, TState, TStatistics>
+{
+  pub fn new(stats: &'a SimStats<TState, TStatistics>) -> Self {
+    Self {
+      title: "Simulation Report".into(),
+      stats,
+      seed: None,
+      ticks: None,
+      wall_time: None,
+      parameters: vec![],
+      #[cfg(feature = "perf")]
+      perf: None,
+    }
+  }
+
+  pub fn title(mut self, title: &str) -> Self {
+    self.title = title.into();
+    self
+  }
+
+  pub fn seed(mut self, seed: u64) -> Self {
+    self.seed = Some(seed);
+    self
+  }
+
+  /// Defaults to the tick of the most recent sample.
+  pub fn ticks(mut self, ticks: usize) -> Self {
+    self.ticks = Some(ticks);
+    self
+  }
+
+  pub fn wall_time(mut self, wall_time: Duration) -> Self {
+    self.wall_time = Some(wall_time);
+    self
+  }
+
+  pub fn parameter(mut self, name: &str, value: impl Display) -> Self {
+    self.parameters.push((name.into(), value.to_string()));
+    self
+  }
+
+  #[cfg(feature = "perf")]
+  pub fn perf(mut self, perf: &'a FoldedSpans) -> Self {
+    self.perf = Some(perf);
+    self
+  }
+
+  pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), ReportError> {
+    std::fs::write(path, self.to_html()?).map_err(ReportError::Io)
+  }
+
+  pub fn to_html(&self) -> Result<String, ReportError> {
+    let mut html = String::new();
+    self.write_html(&mut html)?;
+    Ok(html)
+  }
+
+  fn write_html(&self, out: &mut String) -> Result<(), ReportError> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", Escaped(&self.title))?;
+    writeln!(out, "<style>{}</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>{}</h1>", Escaped(&self.title))?;
+
+    self.write_metadata(out)?;
+    self.write_charts(out)?;
+    self.write_alerts(out)?;
+    self.write_annotations(out)?;
+    self.write_summary(out)?;
+    #[cfg(feature = "perf")]
+    self.write_perf(out)?;
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+  }
+
+  /// A tick, with its ISO 8601 date and time on calendar time axes.
+  fn when(&self, tick: usize) -> String {
+    match self.stats.timestamp(tick) {
+      Some(timestamp) => format!("{} ({})", tick, timestamp),
+      None => tick.to_string(),
+    }
+  }
+
+  fn write_metadata(&self, out: &mut String) -> Result<(), ReportError> {
+    writeln!(out, "<h2>Run</h2>")?;
+    writeln!(out, "<table>")?;
+    if let Some(seed) = self.seed {
+      write_row(out, &["Seed", &seed.to_string()])?;
+    }
+    if let TimeAxis::Calendar(start) = TStatistics::get_time_axis() {
+      write_row(out, &["Start", &start.to_string()])?;
+    }
+    let ticks = self
+      .ticks
+      .or_else(|| self.stats.statistics.last().map(|(tick, _)| *tick))
+      .unwrap_or(0);
+    write_row(out, &["Ticks", &ticks.to_string()])?;
+    write_row(out, &["Samples", &self.stats.statistics.len().to_string()])?;
+    if let Some(wall_time) = self.wall_time {
+      write_row(out, &["Wall time", &format!("{:?}", wall_time)])?;
+    }
+    if let Some(tick) = self.stats.warm_up_end().filter(|tick| *tick > 0) {
+      write_row(out, &["Warm-up ends at tick", &self.when(tick)])?;
+    }
+    if let Some(convergence) = self.stats.convergence() {
+      write_row(out, &["Converged at tick", &self.when(convergence.tick)])?;
+      write_row(
+        out,
+        &["Burn-in ends at tick", &self.when(convergence.burn_in)],
+      )?;
+    }
+    writeln!(out, "</table>")?;
+
+    if !self.parameters.is_empty() {
+      writeln!(out, "<h2>Parameters</h2>")?;
+      writeln!(out, "<table>")?;
+      for (name, value) in self.parameters.iter() {
+        write_row(out, &[name, value])?;
+      }
+      writeln!(out, "</table>")?;
+    }
+    Ok(())
+  }
+
+  fn write_charts(&self, out: &mut String) -> Result<(), ReportError> {
+    writeln!(out, "<h2>Statistics</h2>")?;
+    writeln!(out, "<div class=\"charts\">")?;
+    for (i, group) in TStatistics::get_groups().iter().enumerate() {
+      let highlight = self.stats.alerts.iter().any(|alert| {
+        alert.action == AlertAction::Highlight && alert.groups.contains(&i)
+      });
+      let svg = StatsChart::new(
+        group,
+        &self.stats.statistics,
+        &self.stats.series_min_values[i],
+        &self.stats.series_max_values[i],
+      )
+      .options(ChartOptions {
+        highlight,
+        ..ChartOptions::default()
+      })
+      .annotations(&self.stats.annotations)
+      .render_svg_string(CHART_SIZE)?;
+      writeln!(out, "<figure>{}</figure>", svg)?;
+    }
+    for group in 0..self.stats.distribution_groups().len() {
+      let svg = DistributionChart::new(self.stats, group)
+        .render_svg_string(CHART_SIZE)?;
+      writeln!(out, "<figure>{}</figure>", svg)?;
+    }
+    for group in 0..self.stats.phase_groups().len() {
+      let svg =
+        PhaseChart::new(self.stats, group).render_svg_string(CHART_SIZE)?;
+      writeln!(out, "<figure>{}</figure>", svg)?;
+    }
+    writeln!(out, "</div>")?;
+    Ok(())
+  }
+
+  fn write_alerts(&self, out: &mut String) -> Result<(), ReportError> {
+    if self.stats.alerts.is_empty() {
+      return Ok(());
+    }
+
+    writeln!(out, "<h2>Alerts</h2>")?;
+    writeln!(out, "<table>")?;
+    for alert in self.stats.alerts.iter() {
+      write_row(
+        out,
+        &[
+          &self.when(alert.tick),
+          &alert.label,
+          &alert.value.to_string(),
+          &format!("{:?}", alert.action),
+        ],
+      )?;
+    }
+    writeln!(out, "</table>")?;
+    if let Some(reason) = self.stats.stop_reason() {
+      writeln!(out, "<p>Stopped: {}</p>", Escaped(&reason.to_string()))?;
+    }
+    Ok(())
+  }
+
+  fn write_annotations(&self, out: &mut String) -> Result<(), ReportError> {
+    if self.stats.annotations.is_empty() {
+      return Ok(());
+    }
+
+    writeln!(out, "<h2>Annotations</h2>")?;
+    writeln!(out, "<table>")?;
+    for annotation in self.stats.annotations.iter() {
+      write_row(out, &[&self.when(annotation.tick), &annotation.label])?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+  }
+
+  fn write_summary(&self, out: &mut String) -> Result<(), ReportError> {
+    let (tick, stats) = match self.stats.statistics.last() {
+      Some(last) => last,
+      None => return Ok(()),
+    };
+
+    writeln!(out, "<h2>Final values at tick {}</h2>", self.when(*tick))?;
+    writeln!(out, "<table>")?;
+    for group in TStatistics::get_groups() {
+      writeln!(
+        out,
+        "<tr><th colspan=\"3\">{}</th></tr>",
+        Escaped(&group.title)
+      )?;
+      let derived = group.derived_values(&self.stats.statistics);
+      let values = group
+        .names
+        .iter()
+        .map(|name| stats.get_value(name.clone()))
+        .chain(
+          derived
+            .iter()
+            .map(|points| points.last().map_or(f64::NAN, |(_, v)| *v)),
+        );
+      for (label, value) in group.series_labels().iter().zip(values) {
+        write_row(
+          out,
+          &[
+            label,
+            &group.shown_value(value).to_string(),
+            &group.shown_unit().symbol,
+          ],
+        )?;
+      }
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+  }
+
+  #[cfg(feature = "perf")]
+  fn write_perf(&self, out: &mut String) -> Result<(), ReportError> {
+    let perf = match self.perf {
+      Some(perf) => perf,
+      None => return Ok(()),
+    };
+
+    writeln!(out, "<h2>Performance</h2>")?;
+    let svg = PerfChart::new(perf).render_svg_string(CHART_SIZE)?;
+    writeln!(out, "<figure>{}</figure>", svg)?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>Span</th><th>Total</th><th>Average</th></tr>")?;
+    write_perf_rows(out, perf, 0)?;
+    writeln!(out, "</table>")?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "perf")]
+fn write_perf_rows(
+  out: &mut String,
+  spans: &FoldedSpans,
+  depth: usize,
+) -> Result<(), ReportError> {
+  for (name, span) in spans.spans() {
+    writeln!(
+      out,
+      "<tr><td style=\"padding-left: {}em\">{}</td><td>{}</td><td>{}</td></tr>",
+      depth * 2,
+      Escaped(name),
+      HumanReadableDuration(&span.duration()),
+      HumanReadableDuration(&span.average_duration()),
+    )?;
+    write_perf_rows(out, span.children(), depth + 1)?;
+  }
+  Ok(())
+}
+
+fn write_row(out: &mut String, cells: &[&str]) -> Result<(), ReportError> {
+  write!(out, "<tr>")?;
+  for cell in cells {
+    write!(out, "<td>{}</td>", Escaped(cell))?;
+  }
+  writeln!(out, "</tr>")?;
+  Ok(())
+}
+
+struct Escaped<'a>(&'a str);
+
+impl<'a> Display for Escaped<'a> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for c in self.0.chars() {
+      match c {
+        '<' => f.write_str("&lt;")?,
+        '>' => f.write_str("&gt;")?,
+        '&' => f.write_str("&amp;")?,
+        '"' => f.write_str("&quot;")?,
+        c => f.write_char(c)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }
+.charts { display: flex; flex-wrap: wrap; }
+figure { margin: 0.5em; background: #000; }";
+
+#[derive(Debug)]
+pub enum ReportError {
+  Chart(ChartError),
+  Format(std::fmt::Error),
+  Io(std::io::Error),
+}
+
+impl Display for ReportError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ReportError::Chart(e) => e.fmt(f),
+      ReportError::Format(e) => e.fmt(f),
+      ReportError::Io(e) => e.fmt(f),
+    }
+  }
+}
+
+impl std::error::Error for ReportError {}
+
+impl From<ChartError> for ReportError {
+  fn from(e: ChartError) -> Self {
+    ReportError::Chart(e)
+  }
+}
+
+impl From<std::fmt::Error> for ReportError {
+  fn from(e: std::fmt::Error) -> Self {
+    ReportError::Format(e)
+  }
+}
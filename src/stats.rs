@@ -1,20 +1,129 @@
 use crate::{Simulation, Simulator};
-use std::marker::PhantomData;
+use alert::AlertState;
+use attached::{Attached, AttachedStatistics};
+use convergence::ConvergenceState;
+use incremental::Incremental;
+use schedule::Sampler;
+use std::{fmt::Display, marker::PhantomData};
+use workers::{DerivePool, Derived};
+
+pub mod alert;
+pub mod annotation;
+pub(crate) mod attached;
+pub mod calendar;
+pub mod convergence;
+pub mod derived;
+pub mod distribution;
+pub mod ensemble;
+pub mod hypothesis;
+pub mod incremental;
+pub mod phase;
+pub mod recording;
+pub mod schedule;
+pub mod source;
+pub mod summary;
+pub mod timeseries;
+pub mod unit;
+pub mod workers;
+
+pub use self::{
+  alert::{Alert, AlertAction, AlertCondition, AlertRule},
+  annotation::{write_annotations_csv, Annotation},
+  calendar::{format_duration, DateTime, TimeAxis},
+  convergence::{Convergence, ConvergenceRule, ConvergenceTest},
+  derived::{Derivation, DerivedSeries},
+  distribution::{DistributionGroup, DistributionView, Histogram},
+  ensemble::{EnsembleComparison, EnsembleReport, Measure, SeriesComparison},
+  hypothesis::TestResult,
+  incremental::{ChangeEvents, IncrementalStatistics},
+  phase::{PhaseColoring, PhaseGroup},
+  recording::write_recording,
+  schedule::RecordSchedule,
+  source::{
+    write_source_csv, DistributionDescriptor, GroupDescriptor, PhaseDescriptor,
+    RecordedStats, StatDescriptor, StatisticsSource,
+  },
+  summary::{SeriesSummary, SummaryConfig, SummaryUpdate},
+  timeseries::{
+    autocorrelation, effective_sample_size, integrated_autocorrelation_time,
+    BatchMeans, Spectrum,
+  },
+  unit::{Prefix, Prefixes, Unit},
+  workers::AsyncStatistics,
+};
 
 pub trait Statistics<T>: Sized {
   type TStatID: std::fmt::Display + Clone;
 
   fn get_tick_unit() -> String;
   fn map_tick_unit(tick: usize) -> f64;
+
+  /// The unit `map_tick_unit` maps ticks to, for conversion.
+  fn get_time_unit() -> Unit {
+    Unit::new(&Self::get_tick_unit())
+  }
+
+  /// The unit to show time in, such as days for a model mapping ticks to
+  /// hours. Ignored unless it converts from `get_time_unit`.
+  fn get_display_time_unit() -> Unit {
+    Self::get_time_unit()
+  }
+
+  /// How to label time, such as dates from a start date for a model whose
+  /// time unit converts to seconds.
+  fn get_time_axis() -> TimeAxis {
+    TimeAxis::Numeric
+  }
   fn get_groups() -> Vec<StatisticsGroup<T, Self>>;
   fn get_value(&self, name: Self::TStatID) -> f64;
   fn derive(state: &T) -> Self;
+
+  /// Derives a sample where only the groups flagged in `due` are needed, so
+  /// groups recorded less often can skip expensive work. Values of skipped
+  /// groups should be NaN, which charts, extremes and summaries ignore.
+  fn derive_groups(state: &T, _due: &[bool]) -> Self {
+    Self::derive(state)
+  }
+
+  fn get_distribution_groups() -> Vec<DistributionGroup<T, Self>> {
+    vec![]
+  }
+
+  /// The value of a distribution-valued statistic. Keep the bins of a
+  /// statistic the same across samples so they can be compared over time.
+  fn get_distribution(&self, _name: Self::TStatID) -> Option<Histogram> {
+    None
+  }
+
+  fn get_phase_groups() -> Vec<PhaseGroup<T, Self>> {
+    vec![]
+  }
+
+  fn get_alert_rules() -> Vec<AlertRule<Self::TStatID>> {
+    vec![]
+  }
+
+  /// Rules that must all pass for the run to count as converged.
+  fn get_convergence_rules() -> Vec<ConvergenceRule<Self::TStatID>> {
+    vec![]
+  }
+
+  /// Whether the model has left warm-up, for `WarmUp::UntilPredicate`.
+  fn is_warmed_up(&self) -> bool {
+    true
+  }
 }
 
 pub struct StatisticsGroup<TState, TStatistics: Statistics<TState>> {
   pub title: String,
-  pub unit: String,
+  pub unit: Unit,
+  /// The unit values are shown in, if not `unit`.
+  pub display_unit: Option<Unit>,
   pub names: Vec<TStatistics::TStatID>,
+  /// Series computed from the recorded history, drawn after `names`.
+  pub derived: Vec<DerivedSeries<TStatistics::TStatID>>,
+  /// The group is derived on every this many recorded samples.
+  pub record_every: usize,
   _state: PhantomData<TState>,
   _statistics: PhantomData<TStatistics>,
 }
@@ -24,99 +133,584 @@ impl<TState, TStatistics: Statistics<TState>>
 {
   pub fn new(
     title: &str,
-    unit: &str,
+    unit: impl Into<Unit>,
     names: Vec<TStatistics::TStatID>,
   ) -> Self {
     Self {
       title: title.into(),
       unit: unit.into(),
+      display_unit: None,
       names,
+      derived: vec![],
+      record_every: 1,
       _state: PhantomData,
       _statistics: PhantomData,
     }
   }
 
-  fn get_max_value(&self, stats: &TStatistics) -> f64 {
+  pub fn derived(
+    mut self,
+    series: DerivedSeries<TStatistics::TStatID>,
+  ) -> Self {
+    self.derived.push(series);
+    self
+  }
+
+  /// Derive the group on only every `record_every` recorded samples, through
+  /// `Statistics::derive_groups`.
+  pub fn record_every(mut self, record_every: usize) -> Self {
+    self.record_every = record_every.max(1);
+    self
+  }
+
+  /// Shows values in another unit, such as kJ for a group recorded in J.
+  /// Ignored unless it converts from the group's unit.
+  pub fn display_unit(mut self, unit: Unit) -> Self {
+    self.display_unit = Some(unit);
+    self
+  }
+
+  /// The unit values are shown in.
+  pub fn shown_unit(&self) -> &Unit {
+    self
+      .display_unit
+      .as_ref()
+      .filter(|unit| self.unit.converts_to(unit))
+      .unwrap_or(&self.unit)
+  }
+
+  /// A recorded value in the unit it is shown in.
+  pub fn shown_value(&self, value: f64) -> f64 {
+    self.unit.convert(value, self.shown_unit()).unwrap_or(value)
+  }
+
+  /// The labels of every series, recorded then derived.
+  pub fn series_labels(&self) -> Vec<String> {
     self
       .names
       .iter()
-      .map(|name| stats.get_value(name.clone()))
-      .fold(0.0, f64::max)
+      .map(|name| name.to_string())
+      .chain(self.derived.iter().map(|derived| derived.label.clone()))
+      .collect()
   }
 
-  fn get_min_value(&self, stats: &TStatistics) -> f64 {
+  /// Computes each derived series over `samples`.
+  pub fn derived_values(
+    &self,
+    samples: &[(usize, TStatistics)],
+  ) -> Vec<Vec<(usize, f64)>> {
+    self
+      .derived
+      .iter()
+      .map(|derived| {
+        derived.values(
+          samples,
+          |stats, id| stats.get_value(id),
+          TStatistics::map_tick_unit,
+        )
+      })
+      .collect()
+  }
+
+  /// Each derived series' value at sample `i` of `samples`.
+  pub fn derived_values_at(
+    &self,
+    samples: &[(usize, TStatistics)],
+    i: usize,
+  ) -> Vec<f64> {
+    self
+      .derived
+      .iter()
+      .map(|derived| {
+        derived.value_at(
+          samples,
+          i,
+          |stats, id| stats.get_value(id),
+          TStatistics::map_tick_unit,
+        )
+      })
+      .collect()
+  }
+
+  fn get_values(&self, stats: &TStatistics) -> Vec<f64> {
     self
       .names
       .iter()
       .map(|name| stats.get_value(name.clone()))
-      .fold(0.0, f64::min)
+      .collect()
   }
 }
 
+/// A period at the start of a run whose samples are set aside, so early
+/// transients don't skew extremes, summaries and exports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WarmUp {
+  #[default]
+  None,
+  /// Samples before this tick.
+  Ticks(usize),
+  /// Samples until `Statistics::is_warmed_up` first returns true.
+  UntilPredicate,
+  /// Samples until every convergence rule passes.
+  UntilConverged,
+}
+
 pub struct StatisticsTrackingSimulatorConfig {
-  step: usize,
+  schedule: RecordSchedule,
+  summaries: Option<SummaryConfig>,
+  keep_history: bool,
+  stop_on_convergence: bool,
+  warm_up: WarmUp,
+  keep_warm_up: bool,
 }
 
 impl Default for StatisticsTrackingSimulatorConfig {
   fn default() -> Self {
-    StatisticsTrackingSimulatorConfig { step: 1 }
+    StatisticsTrackingSimulatorConfig {
+      schedule: RecordSchedule::default(),
+      summaries: None,
+      keep_history: true,
+      stop_on_convergence: false,
+      warm_up: WarmUp::None,
+      keep_warm_up: true,
+    }
   }
 }
 
 impl StatisticsTrackingSimulatorConfig {
+  /// Records every `step` ticks.
   pub fn step(mut self, step: usize) -> Self {
-    self.step = step;
+    self.schedule = RecordSchedule::Ticks(step);
+    self
+  }
+
+  pub fn schedule(mut self, schedule: RecordSchedule) -> Self {
+    self.schedule = schedule;
     self
   }
+
+  /// Keep streaming summaries of every series.
+  pub fn summaries(mut self, summaries: SummaryConfig) -> Self {
+    self.summaries = Some(summaries);
+    self
+  }
+
+  /// When false only the most recent sample is kept, which bounds memory on
+  /// long runs. Summaries and extremes still cover every sample.
+  pub fn keep_history(mut self, keep_history: bool) -> Self {
+    self.keep_history = keep_history;
+    self
+  }
+
+  /// Stop headless runs once every convergence rule passes.
+  pub fn stop_on_convergence(mut self, stop_on_convergence: bool) -> Self {
+    self.stop_on_convergence = stop_on_convergence;
+    self
+  }
+
+  pub fn warm_up(mut self, warm_up: WarmUp) -> Self {
+    self.warm_up = warm_up;
+    self
+  }
+
+  /// When false only the most recent warm-up sample is kept, as it also is
+  /// when history isn't kept.
+  pub fn keep_warm_up(mut self, keep_warm_up: bool) -> Self {
+    self.keep_warm_up = keep_warm_up;
+    self
+  }
+
+  fn summarize_every_tick(&self) -> bool {
+    self
+      .summaries
+      .as_ref()
+      .is_some_and(|s| s.get_update() == SummaryUpdate::EveryTick)
+  }
 }
 
 pub struct SimStats<TState, TStatistics: Statistics<TState>> {
+  /// All-time extremes of each group, over every series in the group.
   pub max_values: Vec<f64>,
   pub min_values: Vec<f64>,
+  /// All-time extremes of each series, indexed by group then series.
+  pub series_max_values: Vec<Vec<f64>>,
+  pub series_min_values: Vec<Vec<f64>>,
+  /// Streaming summaries of each series, indexed by group then series. Empty
+  /// unless enabled in the config.
+  pub summaries: Vec<Vec<SeriesSummary>>,
   pub statistics: Vec<(usize, TStatistics)>,
+  /// Samples recorded during warm-up, kept apart from `statistics`.
+  pub warm_up: Vec<(usize, TStatistics)>,
+  /// Labelled events, in the order they were added.
+  pub annotations: Vec<Annotation>,
+  /// Every alert that has fired since warm-up, in order.
+  pub alerts: Vec<Alert>,
+  alert_rules: Vec<AlertState<TStatistics::TStatID>>,
+  convergence_rules: Vec<ConvergenceState<TStatistics::TStatID>>,
+  convergence: Option<Convergence>,
+  stop_on_convergence: bool,
+  stop_reason: Option<StopReason>,
+  warm_up_config: WarmUp,
+  warm_up_end: Option<usize>,
+  keep_warm_up: bool,
+  keep_history: bool,
+  /// How many samples have been recorded, warm-up included.
+  records: usize,
+  descriptors: Vec<GroupDescriptor>,
+  /// The stat IDs of each group, for `StatisticsSource` lookups by index.
+  ids: Vec<Vec<TStatistics::TStatID>>,
+  distribution_descriptors: Vec<DistributionDescriptor>,
+  distribution_ids: Vec<TStatistics::TStatID>,
+  phase_descriptors: Vec<PhaseDescriptor>,
+  phase_ids: Vec<(TStatistics::TStatID, TStatistics::TStatID)>,
   _state: PhantomData<TState>,
 }
 
 impl<TState, TStatistics: Statistics<TState>> SimStats<TState, TStatistics> {
-  fn new(init_state: &TState) -> Self {
-    let stats = TStatistics::derive(init_state);
-    Self {
-      max_values: TStatistics::get_groups()
+  fn new(
+    init_state: &TState,
+    config: &StatisticsTrackingSimulatorConfig,
+  ) -> Self {
+    let groups = TStatistics::get_groups();
+    let distribution_groups = TStatistics::get_distribution_groups();
+    let phase_groups = TStatistics::get_phase_groups();
+    let values: Vec<Vec<_>> = groups
+      .iter()
+      .map(|group| vec![f64::NAN; group.names.len()])
+      .collect();
+    let summaries = match &config.summaries {
+      Some(summary_config) => groups
+        .iter()
+        .map(|group| {
+          group
+            .names
+            .iter()
+            .map(|_| SeriesSummary::new(summary_config))
+            .collect()
+        })
+        .collect(),
+      None => vec![],
+    };
+    let mut sim_stats = Self {
+      max_values: vec![],
+      min_values: vec![],
+      series_max_values: values.clone(),
+      series_min_values: values,
+      summaries,
+      statistics: vec![],
+      warm_up: vec![],
+      annotations: vec![],
+      alerts: vec![],
+      alert_rules: vec![],
+      convergence_rules: TStatistics::get_convergence_rules()
+        .into_iter()
+        .map(|rule| {
+          let groups = Self::groups_showing(&rule.id);
+          ConvergenceState::new(rule, groups)
+        })
+        .collect(),
+      convergence: None,
+      stop_on_convergence: config.stop_on_convergence,
+      stop_reason: None,
+      warm_up_config: config.warm_up,
+      warm_up_end: None,
+      keep_warm_up: config.keep_warm_up && config.keep_history,
+      keep_history: config.keep_history,
+      records: 0,
+      descriptors: groups.iter().map(GroupDescriptor::of).collect(),
+      ids: groups.iter().map(|group| group.names.clone()).collect(),
+      distribution_descriptors: distribution_groups
+        .iter()
+        .map(DistributionDescriptor::of)
+        .collect(),
+      distribution_ids: distribution_groups
         .iter()
-        .map(|group| group.get_max_value(&stats))
+        .map(|group| group.name.clone())
         .collect(),
-      min_values: TStatistics::get_groups()
+      phase_descriptors: phase_groups.iter().map(PhaseDescriptor::of).collect(),
+      phase_ids: phase_groups
         .iter()
-        .map(|group| group.get_min_value(&stats))
+        .map(|group| (group.x.clone(), group.y.clone()))
         .collect(),
-      statistics: vec![(0, stats)],
       _state: PhantomData,
+    };
+    for rule in TStatistics::get_alert_rules() {
+      sim_stats.add_alert_rule(rule);
     }
+    sim_stats.record(0, TStatistics::derive(init_state));
+    sim_stats
+  }
+
+  /// Looks up the summary of a series by stat ID.
+  pub fn summary(&self, id: &TStatistics::TStatID) -> Option<&SeriesSummary>
+  where
+    TStatistics::TStatID: PartialEq,
+  {
+    TStatistics::get_groups()
+      .iter()
+      .enumerate()
+      .find_map(|(i, group)| {
+        let j = group.names.iter().position(|name| name == id)?;
+        self.summaries.get(i)?.get(j)
+      })
+  }
+
+  pub fn annotate(&mut self, tick: usize, label: impl Into<String>) {
+    self.annotations.push(Annotation::new(tick, label));
+  }
+
+  /// Adds a rule on top of those from `Statistics::get_alert_rules`. The
+  /// charts to highlight are found by matching the stat's label.
+  pub fn add_alert_rule(&mut self, rule: AlertRule<TStatistics::TStatID>) {
+    let groups = Self::groups_showing(rule.condition.id());
+    self.alert_rules.push(AlertState::new(rule, groups));
   }
 
-  fn record(&mut self, tick: usize, state: &TState) {
-    let stats = TStatistics::derive(state);
+  /// The groups that show a stat, matched by its label.
+  fn groups_showing(id: &TStatistics::TStatID) -> Vec<usize> {
+    let label = id.to_string();
+    TStatistics::get_groups()
+      .iter()
+      .enumerate()
+      .filter(|(_, group)| {
+        group.names.iter().any(|name| name.to_string() == label)
+      })
+      .map(|(i, _)| i)
+      .collect()
+  }
+
+  /// Why a headless run should stop, if a stopping alert has fired.
+  pub fn stop_reason(&self) -> Option<&StopReason> {
+    self.stop_reason.as_ref()
+  }
+
+  pub fn add_convergence_rule(
+    &mut self,
+    rule: ConvergenceRule<TStatistics::TStatID>,
+  ) {
+    let groups = Self::groups_showing(&rule.id);
+    self
+      .convergence_rules
+      .push(ConvergenceState::new(rule, groups));
+  }
+
+  /// When the convergence rules first all passed, if they have.
+  pub fn convergence(&self) -> Option<Convergence> {
+    self.convergence
+  }
+
+  /// The first tick after warm-up, once the run has converged.
+  pub fn burn_in(&self) -> Option<usize> {
+    self.convergence.map(|convergence| convergence.burn_in)
+  }
+
+  /// The recorded samples from the end of burn-in on, once the run has
+  /// converged.
+  pub fn steady_state(&self) -> Option<&[(usize, TStatistics)]> {
+    let burn_in = self.burn_in()?;
+    let start = self.statistics.partition_point(|(tick, _)| *tick < burn_in);
+    Some(&self.statistics[start..])
+  }
+
+  fn check_convergence(
+    &mut self,
+    tick: usize,
+    stats: &TStatistics,
+    due: &[bool],
+  ) {
+    if self.convergence.is_some() || self.convergence_rules.is_empty() {
+      return;
+    }
+    let mut burn_in = Some(0);
+    for rule in self.convergence_rules.iter_mut() {
+      // As for alerts, stats of groups that weren't derived have no value
+      if rule.groups().is_empty() || rule.groups().iter().any(|g| due[*g]) {
+        rule.push(tick, stats.get_value(rule.id().clone()));
+      }
+      burn_in = burn_in.zip(rule.passing_since()).map(|(a, b)| a.max(b));
+    }
+    let burn_in = match burn_in {
+      Some(burn_in) => burn_in,
+      None => return,
+    };
+
+    let convergence = Convergence { tick, burn_in };
+    self.convergence = Some(convergence);
+    self.annotate(burn_in, "Steady state");
+    if self.stop_on_convergence && self.stop_reason.is_none() {
+      self.stop_reason = Some(StopReason::Converged(convergence));
+    }
+  }
+
+  fn check_alerts(&mut self, tick: usize, stats: &TStatistics, due: &[bool]) {
+    for rule in self.alert_rules.iter_mut() {
+      // Stats only shown in groups that weren't derived have no value
+      if !rule.groups().is_empty()
+        && rule.groups().iter().all(|group| !due[*group])
+      {
+        continue;
+      }
+      let value = stats.get_value(rule.id().clone());
+      let alert = match rule.check(tick, value) {
+        Some(alert) => alert,
+        None => continue,
+      };
+      match alert.action {
+        AlertAction::Log => eprintln!("alert at {}", alert),
+        AlertAction::Stop if self.stop_reason.is_none() => {
+          self.stop_reason = Some(StopReason::Alert(alert.clone()));
+        }
+        _ => {}
+      }
+      self.alerts.push(alert);
+    }
+  }
+
+  fn summarize(&mut self, stats: &TStatistics) {
+    if self.summaries.is_empty() || self.is_warming_up() {
+      return;
+    }
+    for (i, group) in TStatistics::get_groups().iter().enumerate() {
+      for (j, value) in group.get_values(stats).into_iter().enumerate() {
+        self.summaries[i][j].push(value);
+      }
+    }
+  }
+
+  /// Whether samples are still being set aside as warm-up.
+  pub fn is_warming_up(&self) -> bool {
+    self.warm_up_end.is_none()
+  }
+
+  /// The tick of the first sample after warm-up.
+  pub fn warm_up_end(&self) -> Option<usize> {
+    self.warm_up_end
+  }
+
+  /// How many samples have been recorded, warm-up included. Unlike the
+  /// length of `statistics`, this grows when history isn't kept.
+  pub fn records(&self) -> usize {
+    self.records
+  }
+
+  /// Checks whether warm-up ends with this sample.
+  fn update_warm_up(&mut self, tick: usize, stats: &TStatistics) {
+    if self.warm_up_end.is_some() {
+      return;
+    }
+    let warmed_up = match self.warm_up_config {
+      WarmUp::None => true,
+      WarmUp::Ticks(ticks) => tick >= ticks,
+      WarmUp::UntilPredicate => stats.is_warmed_up(),
+      WarmUp::UntilConverged => self.convergence.is_some(),
+    };
+    if warmed_up {
+      self.warm_up_end = Some(tick);
+      if self.warm_up_config != WarmUp::None {
+        self.annotate(tick, "Warm-up ends");
+      }
+    }
+  }
+
+  /// Which groups the sample recorded as number `record` needs. Samples only
+  /// taken for summaries skip every group not recorded each time.
+  fn groups_due(record: Option<usize>) -> Vec<bool> {
+    TStatistics::get_groups()
+      .iter()
+      .map(|group| match record {
+        Some(record) => record % group.record_every == 0,
+        None => group.record_every == 1,
+      })
+      .collect()
+  }
+
+  /// The largest change of any series from the latest sample, relative to
+  /// its previous value.
+  fn relative_change(&self, stats: &TStatistics) -> f64 {
+    let previous = match self.statistics.last().or_else(|| self.warm_up.last())
+    {
+      Some((_, previous)) => previous,
+      None => return 0.0,
+    };
+    TStatistics::get_groups()
+      .iter()
+      .flat_map(|group| {
+        group
+          .get_values(previous)
+          .into_iter()
+          .zip(group.get_values(stats))
+      })
+      .map(|(previous, value)| {
+        (value - previous).abs() / previous.abs().max(f64::EPSILON)
+      })
+      .filter(|change| change.is_finite())
+      .fold(0.0, f64::max)
+  }
+
+  fn record(&mut self, tick: usize, stats: TStatistics) {
+    let due = Self::groups_due(Some(self.records));
+    self.records += 1;
+    self.check_convergence(tick, &stats, &due);
+    self.update_warm_up(tick, &stats);
+    if self.is_warming_up() {
+      if !self.keep_warm_up {
+        self.warm_up.clear();
+      }
+      self.warm_up.push((tick, stats));
+      return;
+    }
+    // Warm-up values are expected to be off, so alerts wait for it to end
+    self.check_alerts(tick, &stats, &due);
+
+    self.summarize(&stats);
+    for (i, group) in TStatistics::get_groups().iter().enumerate() {
+      for (j, value) in group.get_values(&stats).into_iter().enumerate() {
+        // f64::max and f64::min skip NaN, so one bad sample can't poison the
+        // range
+        self.series_max_values[i][j] =
+          f64::max(self.series_max_values[i][j], value);
+        self.series_min_values[i][j] =
+          f64::min(self.series_min_values[i][j], value);
+      }
+    }
+    self.update_group_extremes();
+    if !self.keep_history {
+      self.statistics.clear();
+    }
+    self.statistics.push((tick, stats));
+  }
+
+  fn update_group_extremes(&mut self) {
     self.max_values = self
-      .max_values
+      .series_max_values
       .iter()
-      .zip(
-        TStatistics::get_groups()
-          .iter()
-          .map(|group| group.get_max_value(&stats)),
-      )
-      .map(|(a, b)| f64::max(*a, b))
+      .map(|values| values.iter().copied().fold(f64::NAN, f64::max))
       .collect();
     self.min_values = self
-      .min_values
+      .series_min_values
       .iter()
-      .zip(
-        TStatistics::get_groups()
-          .iter()
-          .map(|group| group.get_min_value(&stats)),
-      )
-      .map(|(a, b)| f64::min(*a, b))
+      .map(|values| values.iter().copied().fold(f64::NAN, f64::min))
       .collect();
-    self.statistics.push((tick, stats));
+  }
+}
+
+/// Why a headless run stopped early.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+  Alert(Alert),
+  Converged(Convergence),
+}
+
+impl Display for StopReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      StopReason::Alert(alert) => write!(f, "alert at {}", alert),
+      StopReason::Converged(convergence) => write!(
+        f,
+        "converged at tick {}, burn-in ends at tick {}",
+        convergence.tick, convergence.burn_in
+      ),
+    }
   }
 }
 
@@ -125,9 +719,15 @@ where
   TSimulation: Simulation,
   TStatistics: Statistics<TSimulation::TState>,
 {
-  config: StatisticsTrackingSimulatorConfig,
   simulator: Simulator<TSimulation>,
   pub stats: SimStats<TSimulation::TState, TStatistics>,
+  sampler: Sampler,
+  /// Derives samples off the simulation thread when set.
+  workers: Option<DerivePool<TSimulation::TState, TStatistics>>,
+  /// Statistics updated every tick, sampled instead of derived when set.
+  incremental: Option<Incremental<TSimulation, TStatistics>>,
+  /// Statistics of other types recorded alongside these.
+  attached: Vec<Box<dyn AttachedStatistics<TSimulation::TState>>>,
   tick: usize,
 }
 
@@ -151,8 +751,11 @@ where
     config: StatisticsTrackingSimulatorConfig,
   ) -> Self {
     Self {
-      config,
-      stats: SimStats::new(&init_state),
+      stats: SimStats::new(&init_state, &config),
+      sampler: Sampler::new::<TSimulation::TState, TStatistics>(&config),
+      workers: None,
+      incremental: None,
+      attached: vec![],
       simulator: Simulator::new(simulation, init_state),
       tick: 0,
     }
@@ -161,21 +764,198 @@ where
   pub fn tick(&mut self) {
     self.simulator.tick();
     self.tick += 1;
-    if self.tick % self.config.step == 0 {
-      self.stats.record(self.tick, self.simulator.state());
+    if let Some(incremental) = &mut self.incremental {
+      incremental.update(&mut self.simulator);
+    }
+    for label in self.simulator.annotations() {
+      for attached in self.attached.iter_mut() {
+        attached.annotate(self.tick, &label);
+      }
+      self.stats.annotate(self.tick, label);
+    }
+    for attached in self.attached.iter_mut() {
+      attached.sample(self.tick, self.simulator.state());
+    }
+    let sample = self
+      .sampler
+      .sample::<TSimulation::TState, TStatistics>(self.tick);
+    if let Some((record, due)) = sample {
+      let state = self.simulator.state();
+      match (&self.incremental, &mut self.workers) {
+        (None, Some(workers)) => workers.submit(state, self.tick, record, due),
+        (incremental, _) => {
+          let stats = match incremental {
+            Some(incremental) => incremental.current(),
+            None => TStatistics::derive_groups(state, &due),
+          };
+          self.apply(Derived {
+            tick: self.tick,
+            record,
+            stats,
+          });
+        }
+      }
+    }
+    self.apply_derived(false);
+  }
+
+  /// Derives samples on `workers` threads from snapshots of the state, so
+  /// slow derivations don't stall the simulation. Samples are still recorded
+  /// in tick order, a little behind the simulation, so adaptive schedules
+  /// react later to changes.
+  pub fn derive_workers(mut self, workers: usize) -> Self
+  where
+    TStatistics: AsyncStatistics<TSimulation::TState>,
+  {
+    self.workers = Some(DerivePool::new(workers));
+    self
+  }
+
+  /// Records statistics of another type alongside these, with their own
+  /// schedule, retention, rules and chart panel.
+  pub fn attach<TOther>(
+    mut self,
+    config: StatisticsTrackingSimulatorConfig,
+  ) -> Self
+  where
+    TSimulation::TState: 'static,
+    TOther: Statistics<TSimulation::TState> + 'static,
+  {
+    self.attached.push(Box::new(Attached::<_, TOther>::new(
+      self.simulator.state(),
+      &config,
+    )));
+    self
+  }
+
+  /// The statistics of an attached type, if one was attached.
+  pub fn attached<TOther>(
+    &self,
+  ) -> Option<&SimStats<TSimulation::TState, TOther>>
+  where
+    TSimulation::TState: 'static,
+    TOther: Statistics<TSimulation::TState> + 'static,
+  {
+    self
+      .attached
+      .iter()
+      .find_map(|attached| attached.as_any().downcast_ref())
+  }
+
+  pub fn attached_mut<TOther>(
+    &mut self,
+  ) -> Option<&mut SimStats<TSimulation::TState, TOther>>
+  where
+    TSimulation::TState: 'static,
+    TOther: Statistics<TSimulation::TState> + 'static,
+  {
+    self
+      .attached
+      .iter_mut()
+      .find_map(|attached| attached.as_any_mut().downcast_mut())
+  }
+
+  #[cfg(feature = "ggez_app")]
+  pub(crate) fn attached_panels(
+    &self,
+  ) -> &[Box<dyn AttachedStatistics<TSimulation::TState>>] {
+    &self.attached
+  }
+
+  /// These statistics then each attached type's, for charting or exporting
+  /// them together.
+  pub fn sources(&self) -> Vec<&dyn StatisticsSource>
+  where
+    TStatistics: 'static,
+  {
+    let mut sources: Vec<&dyn StatisticsSource> = vec![&self.stats];
+    sources.extend(self.attached.iter().map(|attached| attached.source()));
+    sources
+  }
+
+  /// Why the run should stop, from these statistics or any attached ones.
+  pub fn stop_reason(&self) -> Option<&StopReason> {
+    self.stats.stop_reason().or_else(|| {
+      self
+        .attached
+        .iter()
+        .find_map(|attached| attached.stop_reason())
+    })
+  }
+
+  /// Keeps the statistics up to date every tick from the changes the
+  /// simulation reports, and samples them instead of deriving them from the
+  /// state. Takes precedence over `derive_workers`.
+  pub fn incremental(mut self) -> Self
+  where
+    TSimulation: ChangeEvents<TStatistics::TChange>,
+    TStatistics: IncrementalStatistics<TSimulation::TState>,
+  {
+    self.incremental = Some(Incremental::new(self.simulator.state()));
+    self
+  }
+
+  /// Waits for the workers to finish every outstanding sample.
+  pub fn flush(&mut self) {
+    self.apply_derived(true);
+  }
+
+  fn apply_derived(&mut self, wait: bool) {
+    let derived = match &mut self.workers {
+      Some(workers) => workers.take_ready(wait),
+      None => return,
+    };
+    for derived in derived {
+      self.apply(derived);
     }
   }
 
+  fn apply(&mut self, derived: Derived<TStatistics>) {
+    self.sampler.apply(&mut self.stats, derived);
+  }
+
+  /// Ticks up to `ticks` times, stopping early when a stopping alert fires or
+  /// the run converges with `stop_on_convergence` set.
+  pub fn run(&mut self, ticks: usize) -> Option<StopReason> {
+    for _ in 0..ticks {
+      if self.stop_reason().is_some() {
+        break;
+      }
+      self.tick();
+    }
+    self.flush();
+    self.stop_reason().cloned()
+  }
+
   pub fn state(&self) -> &TSimulation::TState {
     self.simulator.state()
   }
 
+  /// Marks an event at the current tick, for events from outside the
+  /// simulation such as an injected command.
+  pub fn annotate(&mut self, label: impl Into<String>) {
+    self.stats.annotate(self.tick, label);
+  }
+
   pub fn statistics(&self) -> impl Iterator<Item = &(usize, TStatistics)> {
     self.stats.statistics.iter()
   }
 
+  /// The latest sample, falling back to warm-up samples during warm-up.
   pub fn most_recent_statistics(&self) -> &(usize, TStatistics) {
-    self.stats.statistics.last().unwrap()
+    self
+      .stats
+      .statistics
+      .last()
+      .or_else(|| self.stats.warm_up.last())
+      .unwrap()
+  }
+
+  pub fn summary(&self, id: &TStatistics::TStatID) -> Option<&SeriesSummary>
+  where
+    TStatistics::TStatID: PartialEq,
+  {
+    self.stats.summary(id)
   }
 }
 
@@ -215,8 +995,8 @@ where
           f,
           "  {:width$} : {} {}",
           name_strings[i],
-          self.stats.get_value(name.clone()),
-          group.unit,
+          group.shown_value(self.stats.get_value(name.clone())),
+          group.shown_unit(),
           width = longest.unwrap(),
         )?;
       }
@@ -224,3 +1004,82 @@ where
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Counts ticks.
+  struct Clock;
+
+  impl Simulation for Clock {
+    type TState = usize;
+
+    fn tick(&mut self, state: &mut usize) {
+      *state += 1;
+    }
+  }
+
+  struct Stats {
+    tick: f64,
+  }
+
+  impl Statistics<usize> for Stats {
+    type TStatID = &'static str;
+
+    fn get_tick_unit() -> String {
+      "tick".into()
+    }
+
+    fn map_tick_unit(tick: usize) -> f64 {
+      tick as f64
+    }
+
+    fn get_groups() -> Vec<StatisticsGroup<usize, Self>> {
+      vec![StatisticsGroup::new("Clock", "", vec!["tick"])]
+    }
+
+    fn get_value(&self, _name: &'static str) -> f64 {
+      self.tick
+    }
+
+    fn derive(state: &usize) -> Self {
+      Self {
+        tick: *state as f64,
+      }
+    }
+
+    fn get_alert_rules() -> Vec<AlertRule<&'static str>> {
+      vec![AlertRule::above("tick", 2.0)]
+    }
+  }
+
+  fn simulator(
+    config: StatisticsTrackingSimulatorConfig,
+  ) -> StatisticsTrackingSimulator<Clock, Stats> {
+    StatisticsTrackingSimulator::with_config(Clock, 0, config)
+  }
+
+  #[test]
+  fn alerts_wait_for_warm_up() {
+    let mut simulator = simulator(
+      StatisticsTrackingSimulatorConfig::default().warm_up(WarmUp::Ticks(10)),
+    );
+    simulator.run(20);
+    assert_eq!(simulator.stats.warm_up_end(), Some(10));
+    assert_eq!(simulator.stats.alerts[0].tick, 10);
+  }
+
+  #[test]
+  fn warm_up_without_history_keeps_one_sample() {
+    let mut simulator = simulator(
+      StatisticsTrackingSimulatorConfig::default()
+        .keep_history(false)
+        .warm_up(WarmUp::Ticks(1_000)),
+    );
+    simulator.run(100);
+    assert!(simulator.stats.is_warming_up());
+    assert_eq!(simulator.stats.warm_up.len(), 1);
+    assert_eq!(simulator.most_recent_statistics().0, 100);
+  }
+}
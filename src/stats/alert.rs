@@ -0,0 +1,182 @@
+use std::fmt::Display;
+
+/// What happens when an alert rule fires.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlertAction {
+  /// Write the alert to stderr.
+  #[default]
+  Log,
+  /// Pause the app.
+  Pause,
+  /// Highlight the charts showing the stat.
+  Highlight,
+  /// Stop a headless run. The app pauses instead.
+  Stop,
+}
+
+#[derive(Clone, Debug)]
+pub enum AlertCondition<TStatID> {
+  Below {
+    id: TStatID,
+    threshold: f64,
+  },
+  Above {
+    id: TStatID,
+    threshold: f64,
+  },
+  /// The relative change from the first finite value exceeds `fraction`.
+  Drift {
+    id: TStatID,
+    fraction: f64,
+  },
+  /// The value is NaN or infinite.
+  NotFinite {
+    id: TStatID,
+  },
+}
+
+impl<TStatID> AlertCondition<TStatID> {
+  pub fn id(&self) -> &TStatID {
+    match self {
+      AlertCondition::Below { id, .. }
+      | AlertCondition::Above { id, .. }
+      | AlertCondition::Drift { id, .. }
+      | AlertCondition::NotFinite { id } => id,
+    }
+  }
+}
+
+/// A condition on one stat, checked every time a sample is recorded.
+#[derive(Clone, Debug)]
+pub struct AlertRule<TStatID> {
+  pub label: String,
+  pub condition: AlertCondition<TStatID>,
+  pub action: AlertAction,
+}
+
+impl<TStatID: Display + Clone> AlertRule<TStatID> {
+  pub fn below(id: TStatID, threshold: f64) -> Self {
+    Self::new(
+      format!("{} < {}", id, threshold),
+      AlertCondition::Below { id, threshold },
+    )
+  }
+
+  pub fn above(id: TStatID, threshold: f64) -> Self {
+    Self::new(
+      format!("{} > {}", id, threshold),
+      AlertCondition::Above { id, threshold },
+    )
+  }
+
+  pub fn drift(id: TStatID, fraction: f64) -> Self {
+    Self::new(
+      format!("{} drift > {}%", id, fraction * 100.0),
+      AlertCondition::Drift { id, fraction },
+    )
+  }
+
+  pub fn not_finite(id: TStatID) -> Self {
+    Self::new(
+      format!("{} is not finite", id),
+      AlertCondition::NotFinite { id },
+    )
+  }
+
+  fn new(label: String, condition: AlertCondition<TStatID>) -> Self {
+    Self {
+      label,
+      condition,
+      action: AlertAction::default(),
+    }
+  }
+
+  pub fn action(mut self, action: AlertAction) -> Self {
+    self.action = action;
+    self
+  }
+
+  pub fn label(mut self, label: &str) -> Self {
+    self.label = label.into();
+    self
+  }
+}
+
+/// A rule that fired.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+  pub tick: usize,
+  pub label: String,
+  pub action: AlertAction,
+  pub value: f64,
+  /// The line groups showing the stat.
+  pub groups: Vec<usize>,
+}
+
+impl Display for Alert {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "tick {}: {} ({})", self.tick, self.label, self.value)
+  }
+}
+
+/// A rule and what it has seen so far.
+pub(crate) struct AlertState<TStatID> {
+  rule: AlertRule<TStatID>,
+  groups: Vec<usize>,
+  baseline: Option<f64>,
+  active: bool,
+}
+
+impl<TStatID: Clone> AlertState<TStatID> {
+  pub fn new(rule: AlertRule<TStatID>, groups: Vec<usize>) -> Self {
+    Self {
+      rule,
+      groups,
+      baseline: None,
+      active: false,
+    }
+  }
+
+  pub fn id(&self) -> &TStatID {
+    self.rule.condition.id()
+  }
+
+  pub fn groups(&self) -> &[usize] {
+    &self.groups
+  }
+
+  /// Checks the rule against a new value. Alerts only fire when the
+  /// condition starts to hold, not on every sample while it holds.
+  pub fn check(&mut self, tick: usize, value: f64) -> Option<Alert> {
+    let holds = match self.rule.condition {
+      AlertCondition::Below { threshold, .. } => value < threshold,
+      AlertCondition::Above { threshold, .. } => value > threshold,
+      AlertCondition::Drift { fraction, .. } => {
+        if self.baseline.is_none() && value.is_finite() {
+          self.baseline = Some(value);
+        }
+        self.baseline.is_some_and(|baseline| {
+          let change = (value - baseline).abs();
+          if baseline == 0.0 {
+            change > fraction
+          } else {
+            change / baseline.abs() > fraction
+          }
+        })
+      }
+      AlertCondition::NotFinite { .. } => !value.is_finite(),
+    };
+    let fired = holds && !self.active;
+    self.active = holds;
+    if !fired {
+      return None;
+    }
+    Some(Alert {
+      tick,
+      label: self.rule.label.clone(),
+      action: self.rule.action,
+      value,
+      groups: self.groups.clone(),
+    })
+  }
+}
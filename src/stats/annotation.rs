@@ -0,0 +1,44 @@
+use super::{StatisticsSource, TimeAxis};
+use std::io::Write;
+
+/// A labelled event at a tick, such as an extinction or a parameter change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+  pub tick: usize,
+  pub label: String,
+}
+
+impl Annotation {
+  pub fn new(tick: usize, label: impl Into<String>) -> Self {
+    Self {
+      tick,
+      label: label.into(),
+    }
+  }
+}
+
+/// Writes a source's annotations as `tick,label` rows, quoting labels.
+/// Sources on calendar time axes add an ISO 8601 `timestamp` column after
+/// `tick`.
+pub fn write_annotations_csv<W: Write>(
+  source: &dyn StatisticsSource,
+  mut writer: W,
+) -> std::io::Result<()> {
+  let calendar = matches!(source.time_axis(), TimeAxis::Calendar(_));
+  if calendar {
+    writeln!(writer, "tick,timestamp,label")?;
+  } else {
+    writeln!(writer, "tick,label")?;
+  }
+  for annotation in source.annotations() {
+    write!(writer, "{},", annotation.tick)?;
+    if calendar {
+      if let Some(timestamp) = source.timestamp(annotation.tick) {
+        write!(writer, "{}", timestamp)?;
+      }
+      write!(writer, ",")?;
+    }
+    writeln!(writer, "\"{}\"", annotation.label.replace('"', "\"\""))?;
+  }
+  Ok(())
+}
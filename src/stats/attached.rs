@@ -0,0 +1,101 @@
+#[cfg(feature = "ggez_app")]
+use super::Alert;
+use super::{
+  schedule::Sampler, workers::Derived, SimStats, Statistics, StatisticsSource,
+  StatisticsTrackingSimulatorConfig, StopReason,
+};
+use std::any::Any;
+
+/// Statistics of another type recorded alongside a tracker's own, with their
+/// own config.
+pub(crate) trait AttachedStatistics<TState> {
+  fn sample(&mut self, tick: usize, state: &TState);
+
+  fn annotate(&mut self, tick: usize, label: &str);
+
+  fn stop_reason(&self) -> Option<&StopReason>;
+
+  /// Every alert that has fired, in order.
+  #[cfg(feature = "ggez_app")]
+  fn alerts(&self) -> &[Alert];
+
+  /// How many samples have been recorded, to tell when charts are stale.
+  #[cfg(feature = "ggez_app")]
+  fn records(&self) -> usize;
+
+  fn source(&self) -> &dyn StatisticsSource;
+
+  fn as_any(&self) -> &dyn Any;
+
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub(crate) struct Attached<TState, TStatistics: Statistics<TState>> {
+  stats: SimStats<TState, TStatistics>,
+  sampler: Sampler,
+}
+
+impl<TState, TStatistics: Statistics<TState>> Attached<TState, TStatistics> {
+  pub fn new(
+    state: &TState,
+    config: &StatisticsTrackingSimulatorConfig,
+  ) -> Self {
+    Self {
+      stats: SimStats::new(state, config),
+      sampler: Sampler::new::<TState, TStatistics>(config),
+    }
+  }
+}
+
+impl<TState, TStatistics> AttachedStatistics<TState>
+  for Attached<TState, TStatistics>
+where
+  TState: 'static,
+  TStatistics: Statistics<TState> + 'static,
+{
+  fn sample(&mut self, tick: usize, state: &TState) {
+    if let Some((record, due)) =
+      self.sampler.sample::<TState, TStatistics>(tick)
+    {
+      let stats = TStatistics::derive_groups(state, &due);
+      self.sampler.apply(
+        &mut self.stats,
+        Derived {
+          tick,
+          record,
+          stats,
+        },
+      );
+    }
+  }
+
+  fn annotate(&mut self, tick: usize, label: &str) {
+    self.stats.annotate(tick, label);
+  }
+
+  fn stop_reason(&self) -> Option<&StopReason> {
+    self.stats.stop_reason()
+  }
+
+  #[cfg(feature = "ggez_app")]
+  fn alerts(&self) -> &[Alert] {
+    &self.stats.alerts
+  }
+
+  #[cfg(feature = "ggez_app")]
+  fn records(&self) -> usize {
+    self.stats.records()
+  }
+
+  fn source(&self) -> &dyn StatisticsSource {
+    &self.stats
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    &self.stats
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    &mut self.stats
+  }
+}
@@ -0,0 +1,408 @@
+use std::fmt::{self, Display};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// A UTC date and time, to the second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+  /// Seconds since 1970-01-01T00:00:00Z.
+  seconds: i64,
+}
+
+impl DateTime {
+  pub fn from_unix_seconds(seconds: i64) -> Self {
+    Self { seconds }
+  }
+
+  pub fn unix_seconds(&self) -> i64 {
+    self.seconds
+  }
+
+  /// Midnight at the start of a day. Months and days count from 1, and
+  /// overflow into the next month or year.
+  pub fn ymd(year: i64, month: u32, day: u32) -> Self {
+    Self {
+      seconds: days_from_civil(year, month as i64, day as i64) * DAY,
+    }
+  }
+
+  /// This date at a time of day.
+  pub fn and_hms(self, hour: u32, minute: u32, second: u32) -> Self {
+    let midnight = self.seconds.div_euclid(DAY) * DAY;
+    Self {
+      seconds: midnight
+        + hour as i64 * HOUR
+        + minute as i64 * MINUTE
+        + second as i64,
+    }
+  }
+
+  /// This time plus `seconds`, to the nearest second.
+  pub fn add_seconds(&self, seconds: f64) -> Self {
+    Self {
+      seconds: self.seconds + seconds.round() as i64,
+    }
+  }
+
+  /// The year, month and day.
+  pub fn date(&self) -> (i64, u32, u32) {
+    let (year, month, day) = civil_from_days(self.seconds.div_euclid(DAY));
+    (year, month as u32, day as u32)
+  }
+
+  /// The hour, minute and second.
+  pub fn time(&self) -> (u32, u32, u32) {
+    let seconds = self.seconds.rem_euclid(DAY);
+    (
+      (seconds / HOUR) as u32,
+      (seconds % HOUR / MINUTE) as u32,
+      (seconds % MINUTE) as u32,
+    )
+  }
+
+  /// Parses an ISO 8601 date, such as `2024-03-01`, or date and time, such
+  /// as `2024-03-01T06:00:00Z`. Times may have fractional seconds, rounded
+  /// to the nearest second, and a `±hh:mm` offset from UTC.
+  pub fn parse_iso(text: &str) -> Option<Self> {
+    let text = text.trim().trim_end_matches('Z');
+    let (date, time) = match text.split_once(['T', ' ']) {
+      Some((date, time)) => (date, Some(time)),
+      None => (text, None),
+    };
+    // Years may be negative, so split the month and day off the end
+    let mut parts = date.rsplitn(3, '-');
+    let day = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let year = parts.next()?.parse().ok()?;
+    let date = Self::ymd(year, month, day);
+    // Days past the end of the month would roll into the next
+    if date.date() != (year, month, day) {
+      return None;
+    }
+    let time = match time {
+      Some(time) => time,
+      None => return Some(date),
+    };
+    let (time, offset) = match time.find(['+', '-']) {
+      Some(i) => (&time[..i], parse_offset(&time[i..])?),
+      None => (time, 0),
+    };
+    let (time, fraction) = match time.split_once('.') {
+      Some((time, fraction)) if digits(fraction) => {
+        (time, format!("0.{}", fraction).parse::<f64>().ok()?)
+      }
+      Some(_) => return None,
+      None => (time, 0.0),
+    };
+    let mut parts = time.split(':').map(|part| match digits(part) {
+      true => part.parse::<u32>().ok(),
+      false => None,
+    });
+    let hour = parts.next()??;
+    let minute = parts.next().unwrap_or(Some(0))?;
+    let second = parts.next().unwrap_or(Some(0))?;
+    if hour > 23 || minute > 59 || second > 60 || parts.next().is_some() {
+      return None;
+    }
+    Some(
+      date
+        .and_hms(hour, minute, second)
+        .add_seconds(fraction - offset as f64),
+    )
+  }
+
+  /// A label that tells apart times across `span` seconds: the date for
+  /// spans of days, or the date and time for shorter ones.
+  pub fn label(&self, span: f64) -> String {
+    let (year, month, day) = self.date();
+    let (hour, minute, _) = self.time();
+    if span >= 3.0 * DAY as f64 {
+      format!("{}-{:02}-{:02}", four_digits(year), month, day)
+    } else {
+      format!("{:02}-{:02} {:02}:{:02}", month, day, hour, minute)
+    }
+  }
+}
+
+impl Display for DateTime {
+  /// ISO 8601, as `2024-03-01T06:00:00Z`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (year, month, day) = self.date();
+    let (hour, minute, second) = self.time();
+    write!(
+      f,
+      "{}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+      four_digits(year),
+      month,
+      day,
+      hour,
+      minute,
+      second
+    )
+  }
+}
+
+/// Whether `text` is a non-empty run of ASCII digits.
+fn digits(text: &str) -> bool {
+  !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// The seconds east of UTC of an offset such as `+05:30`, `-0800` or `+01`.
+fn parse_offset(text: &str) -> Option<i64> {
+  let sign = if text.starts_with('-') { -1 } else { 1 };
+  let text = &text[1..];
+  let (hours, minutes) = match text.split_once(':') {
+    Some(split) => split,
+    None if text.len() == 4 => text.split_at(2),
+    None => (text, "00"),
+  };
+  if hours.len() != 2 || minutes.len() != 2 {
+    return None;
+  }
+  let (hours, minutes) = match (digits(hours), digits(minutes)) {
+    (true, true) => (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?),
+    _ => return None,
+  };
+  if hours > 23 || minutes > 59 {
+    return None;
+  }
+  Some(sign * (hours * HOUR + minutes * MINUTE))
+}
+
+/// A year padded to four digits, after any sign.
+fn four_digits(year: i64) -> String {
+  if year < 0 {
+    format!("-{:04}", -year)
+  } else {
+    format!("{:04}", year)
+  }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  // Overflowing months roll into the following years
+  let year = year + (month - 1).div_euclid(12);
+  let month = (month - 1).rem_euclid(12) + 1;
+  // Counting years from March puts leap days at the end
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+  let day_of_era =
+    year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+    - day_of_era / 146_096)
+    / 365;
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_from_march = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+  let month = if month_from_march < 10 {
+    month_from_march + 3
+  } else {
+    month_from_march - 9
+  };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+/// A duration in its two largest units, such as `3d 4h` or `2m 30s`.
+pub fn format_duration(seconds: f64) -> String {
+  if !seconds.is_finite() {
+    return seconds.to_string();
+  }
+  let sign = if seconds < 0.0 { "-" } else { "" };
+  let seconds = seconds.abs();
+  if seconds < 1.0 {
+    let fraction = format!("{:.3}", seconds);
+    let fraction = fraction.trim_end_matches('0').trim_end_matches('.');
+    return format!("{}{}s", sign, fraction);
+  }
+  let mut remaining = seconds.round() as i64;
+  let parts: Vec<String> = [(DAY, "d"), (HOUR, "h"), (MINUTE, "m"), (1, "s")]
+    .iter()
+    .map(|(size, unit)| {
+      let count = remaining / size;
+      remaining %= size;
+      (count, unit)
+    })
+    .skip_while(|(count, _)| *count == 0)
+    .take(2)
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, unit)| format!("{}{}", count, unit))
+    .collect();
+  format!("{}{}", sign, parts.join(" "))
+}
+
+/// How time is labelled on chart axes and in readouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TimeAxis {
+  /// Times as numbers in the display time unit.
+  #[default]
+  Numeric,
+  /// Time elapsed since time 0, such as `3d 4h`.
+  Duration,
+  /// Dates, counting time 0 as `start`. Exports gain ISO timestamps.
+  Calendar(DateTime),
+}
+
+impl TimeAxis {
+  /// The label of a time `seconds` after time 0, with `span` seconds in
+  /// view, or `None` for numeric axes.
+  pub fn label(&self, seconds: f64, span: f64) -> Option<String> {
+    match self {
+      TimeAxis::Numeric => None,
+      TimeAxis::Duration => Some(format_duration(seconds)),
+      TimeAxis::Calendar(start) => Some(start.add_seconds(seconds).label(span)),
+    }
+  }
+
+  /// The axis description, or `None` for numeric axes, which are described
+  /// by their unit.
+  pub fn description(&self) -> Option<&'static str> {
+    match self {
+      TimeAxis::Numeric => None,
+      TimeAxis::Duration => Some("elapsed"),
+      TimeAxis::Calendar(_) => Some("date (UTC)"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn days_match_reference_dates() {
+    for (date, days) in [
+      ((1970, 1, 1), 0),
+      ((1969, 12, 31), -1),
+      ((2000, 2, 29), 11_016),
+      ((2000, 3, 1), 11_017),
+      ((1900, 1, 1), -25_567),
+      ((1900, 3, 1), -25_508),
+      ((2100, 2, 28), 47_540),
+      ((2100, 3, 1), 47_541),
+      ((2024, 2, 29), 19_782),
+      ((1, 1, 1), -719_162),
+    ] {
+      assert_eq!(days_from_civil(date.0, date.1, date.2), days, "{:?}", date);
+      assert_eq!(civil_from_days(days), date);
+    }
+  }
+
+  #[test]
+  fn days_round_trip() {
+    // Six centuries either side of the epoch, leap rules and all
+    let mut previous = civil_from_days(-220_000);
+    for days in -219_999..220_000 {
+      let date = civil_from_days(days);
+      assert_eq!(days_from_civil(date.0, date.1, date.2), days);
+      // Each day follows the last, into a new month only after its last day
+      let (year, month, day) = previous;
+      let last = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+      };
+      let next = match (month, day == last) {
+        (12, true) => (year + 1, 1, 1),
+        (_, true) => (year, month + 1, 1),
+        _ => (year, month, day + 1),
+      };
+      assert_eq!(date, next);
+      previous = date;
+    }
+  }
+
+  #[test]
+  fn leap_days() {
+    for (year, leap) in [
+      (2000, true),
+      (2024, true),
+      (1900, false),
+      (2100, false),
+      (2023, false),
+    ] {
+      let text = format!("{}-02-29", year);
+      assert_eq!(DateTime::parse_iso(&text).is_some(), leap, "{}", text);
+    }
+    assert!(DateTime::parse_iso("2024-04-31").is_none());
+    assert!(DateTime::parse_iso("2024-13-01").is_none());
+  }
+
+  #[test]
+  fn iso_round_trips() {
+    for seconds in [0, -1, 951_782_400, 4_107_542_399, -2_208_988_800] {
+      let time = DateTime::from_unix_seconds(seconds);
+      assert_eq!(DateTime::parse_iso(&time.to_string()), Some(time));
+    }
+    assert_eq!(
+      DateTime::from_unix_seconds(-1).to_string(),
+      "1969-12-31T23:59:59Z"
+    );
+    assert_eq!(DateTime::ymd(-1, 3, 1).to_string(), "-0001-03-01T00:00:00Z");
+    assert_eq!(
+      DateTime::parse_iso("-0001-03-01"),
+      Some(DateTime::ymd(-1, 3, 1))
+    );
+  }
+
+  #[test]
+  fn iso_offsets_and_fractions() {
+    let utc = DateTime::ymd(2024, 3, 1).and_hms(6, 0, 0);
+    for text in [
+      "2024-03-01T06:00:00Z",
+      "2024-03-01T06:00:00+00:00",
+      "2024-03-01T08:00:00+02:00",
+      "2024-03-01T00:30:00-0530",
+      "2024-03-01T07:00+01",
+      "2024-03-01 06:00:00.4",
+      "2024-03-01T05:59:59.5Z",
+    ] {
+      assert_eq!(DateTime::parse_iso(text), Some(utc), "{}", text);
+    }
+    // Offsets can cross midnight
+    assert_eq!(
+      DateTime::parse_iso("2024-03-01T01:00:00+02:00"),
+      Some(DateTime::ymd(2024, 2, 29).and_hms(23, 0, 0))
+    );
+    for text in [
+      "2024-03-01T06:00:00+2:00",
+      "2024-03-01T06:00:00+24:00",
+      "2024-03-01T06:00:00.",
+      "2024-03-01T06:00:00.5x",
+      "2024-03-01T24:00:00",
+      "2024-03-01T06:00:00:00",
+      "2024-03-01T",
+    ] {
+      assert_eq!(DateTime::parse_iso(text), None, "{}", text);
+    }
+  }
+
+  #[test]
+  fn durations() {
+    for (seconds, text) in [
+      (0.0, "0s"),
+      (0.25, "0.25s"),
+      (1.0, "1s"),
+      (90.0, "1m 30s"),
+      (3600.0, "1h"),
+      (90_061.0, "1d 1h"),
+      (-90.0, "-1m 30s"),
+      (f64::INFINITY, "inf"),
+    ] {
+      assert_eq!(format_duration(seconds), text);
+    }
+  }
+}
@@ -0,0 +1,164 @@
+use super::summary::Welford;
+use std::{collections::VecDeque, fmt::Display};
+
+#[derive(Clone, Debug)]
+pub enum ConvergenceTest {
+  /// The mean and standard deviation of the two halves of the window agree
+  /// to within `tolerance`, relative to the series' scale.
+  Stability { window: usize, tolerance: f64 },
+  /// The range of the window is within `tolerance` of its mean.
+  RelativeChange { window: usize, tolerance: f64 },
+  /// Geweke's test: the means of the first 10% and last 50% of the window
+  /// differ by less than `z` standard errors.
+  Stationarity { window: usize, z: f64 },
+}
+
+impl ConvergenceTest {
+  fn window(&self) -> usize {
+    match self {
+      ConvergenceTest::Stability { window, .. }
+      | ConvergenceTest::RelativeChange { window, .. }
+      | ConvergenceTest::Stationarity { window, .. } => *window,
+    }
+  }
+
+  fn passes(&self, values: &[f64]) -> bool {
+    match self {
+      ConvergenceTest::Stability { tolerance, .. } => {
+        let (a, b) = values.split_at(values.len() / 2);
+        let (a, b) = (moments(a), moments(b));
+        let scale = f64::max(a.mean().abs(), b.mean().abs()).max(f64::EPSILON);
+        let (sd_a, sd_b) = (a.variance().sqrt(), b.variance().sqrt());
+        (a.mean() - b.mean()).abs() <= tolerance * scale
+          && (sd_a - sd_b).abs()
+            <= tolerance * f64::max(sd_a.max(sd_b), tolerance * scale)
+      }
+      ConvergenceTest::RelativeChange { tolerance, .. } => {
+        let all = moments(values);
+        let (min, max) = values
+          .iter()
+          .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+          });
+        max - min <= tolerance * all.mean().abs()
+      }
+      ConvergenceTest::Stationarity { z, .. } => {
+        let n = values.len();
+        let a = moments(&values[..(n / 10).max(2)]);
+        let b = moments(&values[n - n / 2..]);
+        let error = (a.variance() / a.count() as f64
+          + b.variance() / b.count() as f64)
+          .sqrt();
+        let difference = (a.mean() - b.mean()).abs();
+        // Constant series have no error, and are stationary if they agree
+        if error == 0.0 {
+          difference == 0.0
+        } else {
+          difference / error < *z
+        }
+      }
+    }
+  }
+}
+
+pub(crate) fn moments(values: &[f64]) -> Welford {
+  let mut moments = Welford::default();
+  for value in values {
+    moments.push(*value);
+  }
+  moments
+}
+
+/// A test on the most recent recorded samples of one stat.
+#[derive(Clone, Debug)]
+pub struct ConvergenceRule<TStatID> {
+  pub id: TStatID,
+  pub test: ConvergenceTest,
+}
+
+impl<TStatID: Display + Clone> ConvergenceRule<TStatID> {
+  pub fn stable(id: TStatID, window: usize, tolerance: f64) -> Self {
+    Self {
+      id,
+      test: ConvergenceTest::Stability {
+        window: window.max(4),
+        tolerance,
+      },
+    }
+  }
+
+  pub fn relative_change(id: TStatID, window: usize, tolerance: f64) -> Self {
+    Self {
+      id,
+      test: ConvergenceTest::RelativeChange {
+        window: window.max(2),
+        tolerance,
+      },
+    }
+  }
+
+  pub fn stationary(id: TStatID, window: usize, z: f64) -> Self {
+    Self {
+      id,
+      test: ConvergenceTest::Stationarity {
+        window: window.max(20),
+        z,
+      },
+    }
+  }
+}
+
+/// When every convergence rule first passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Convergence {
+  /// The tick of the sample where the rules passed.
+  pub tick: usize,
+  /// The first tick of the latest rule window. Samples before it are warm-up.
+  pub burn_in: usize,
+}
+
+/// A rule and the window of samples it tests.
+pub(crate) struct ConvergenceState<TStatID> {
+  rule: ConvergenceRule<TStatID>,
+  /// The groups that show the stat.
+  groups: Vec<usize>,
+  window: VecDeque<(usize, f64)>,
+}
+
+impl<TStatID> ConvergenceState<TStatID> {
+  pub fn new(rule: ConvergenceRule<TStatID>, groups: Vec<usize>) -> Self {
+    Self {
+      window: VecDeque::with_capacity(rule.test.window()),
+      rule,
+      groups,
+    }
+  }
+
+  pub fn id(&self) -> &TStatID {
+    &self.rule.id
+  }
+
+  pub fn groups(&self) -> &[usize] {
+    &self.groups
+  }
+
+  pub fn push(&mut self, tick: usize, value: f64) {
+    if self.window.len() == self.rule.test.window() {
+      self.window.pop_front();
+    }
+    self.window.push_back((tick, value));
+  }
+
+  /// The first tick of the window, if it is full and passes the test.
+  pub fn passing_since(&self) -> Option<usize> {
+    if self.window.len() < self.rule.test.window() {
+      return None;
+    }
+    let values: Vec<_> = self.window.iter().map(|(_, value)| *value).collect();
+    if self.rule.test.passes(&values) {
+      self.window.front().map(|(tick, _)| *tick)
+    } else {
+      None
+    }
+  }
+}
@@ -0,0 +1,295 @@
+use std::fmt::Display;
+
+/// A series computed from the recorded history of other series, rather than
+/// from the simulation state.
+#[derive(Clone, Debug)]
+pub struct DerivedSeries<TStatID> {
+  pub label: String,
+  pub derivation: Derivation<TStatID>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Derivation<TStatID> {
+  /// The mean of the last `window` samples.
+  MovingAverage { id: TStatID, window: usize },
+  /// An exponential moving average over samples.
+  Ema { id: TStatID, alpha: f64 },
+  /// The change since the last sample with a finite value, per `per` units
+  /// of mapped time.
+  Rate { id: TStatID, per: f64 },
+  /// The running total of every sample so far.
+  CumulativeSum { id: TStatID },
+  /// One series divided by another.
+  Ratio {
+    numerator: TStatID,
+    denominator: TStatID,
+  },
+}
+
+impl<TStatID: Display + Clone> DerivedSeries<TStatID> {
+  pub fn moving_average(id: TStatID, window: usize) -> Self {
+    Self {
+      label: format!("{} (MA {})", id, window),
+      derivation: Derivation::MovingAverage {
+        id,
+        window: window.max(1),
+      },
+    }
+  }
+
+  pub fn ema(id: TStatID, alpha: f64) -> Self {
+    Self {
+      label: format!("{} (EMA {})", id, alpha),
+      derivation: Derivation::Ema { id, alpha },
+    }
+  }
+
+  pub fn rate(id: TStatID, per: f64) -> Self {
+    Self {
+      label: format!("{} per {}", id, per),
+      derivation: Derivation::Rate { id, per },
+    }
+  }
+
+  pub fn cumulative_sum(id: TStatID) -> Self {
+    Self {
+      label: format!("{} (total)", id),
+      derivation: Derivation::CumulativeSum { id },
+    }
+  }
+
+  pub fn ratio(numerator: TStatID, denominator: TStatID) -> Self {
+    Self {
+      label: format!("{} / {}", numerator, denominator),
+      derivation: Derivation::Ratio {
+        numerator,
+        denominator,
+      },
+    }
+  }
+
+  pub fn label(mut self, label: &str) -> Self {
+    self.label = label.into();
+    self
+  }
+
+  /// Computes the series over `samples`. `value` reads a stat from a sample
+  /// and `time` maps a tick to time units. Samples without a finite value,
+  /// such as those of groups skipped on a record, are left out of averages
+  /// and totals, and rates span them.
+  pub fn values<S, V, T>(
+    &self,
+    samples: &[(usize, S)],
+    value: V,
+    time: T,
+  ) -> Vec<(usize, f64)>
+  where
+    V: Fn(&S, TStatID) -> f64,
+    T: Fn(usize) -> f64,
+  {
+    match &self.derivation {
+      Derivation::MovingAverage { id, window } => {
+        let finite = |i: usize| {
+          Some(value(&samples[i].1, id.clone()))
+            .filter(|value| value.is_finite())
+        };
+        // The sum and count of the finite values in the window
+        let (mut sum, mut count) = (0.0, 0);
+        (0..samples.len())
+          .map(|i| {
+            if let Some(value) = finite(i) {
+              sum += value;
+              count += 1;
+            }
+            if i >= *window {
+              if let Some(value) = finite(i - window) {
+                sum -= value;
+                count -= 1;
+              }
+            }
+            let mean = if count > 0 {
+              sum / count as f64
+            } else {
+              f64::NAN
+            };
+            (samples[i].0, mean)
+          })
+          .collect()
+      }
+      Derivation::Ema { id, alpha } => {
+        let mut ewma: Option<f64> = None;
+        samples
+          .iter()
+          .map(|(tick, stats)| {
+            let v = value(stats, id.clone());
+            if v.is_finite() {
+              ewma = Some(match ewma {
+                Some(ewma) => ewma + alpha * (v - ewma),
+                None => v,
+              });
+            }
+            (*tick, ewma.unwrap_or(f64::NAN))
+          })
+          .collect()
+      }
+      Derivation::Rate { id, per } => {
+        // The last sample with a finite value, and that value
+        let mut previous: Option<(usize, f64)> = None;
+        samples
+          .iter()
+          .map(|(tick, stats)| {
+            let v = value(stats, id.clone());
+            if !v.is_finite() {
+              return (*tick, f64::NAN);
+            }
+            let rate = previous.map_or(f64::NAN, |(prev_tick, prev)| {
+              (v - prev) / (time(*tick) - time(prev_tick)) * per
+            });
+            previous = Some((*tick, v));
+            (*tick, rate)
+          })
+          .collect()
+      }
+      Derivation::CumulativeSum { id } => {
+        let mut sum = 0.0;
+        samples
+          .iter()
+          .map(|(tick, stats)| {
+            let v = value(stats, id.clone());
+            if v.is_finite() {
+              sum += v;
+            }
+            (*tick, sum)
+          })
+          .collect()
+      }
+      Derivation::Ratio {
+        numerator,
+        denominator,
+      } => samples
+        .iter()
+        .map(|(tick, stats)| {
+          (
+            *tick,
+            value(stats, numerator.clone()) / value(stats, denominator.clone()),
+          )
+        })
+        .collect(),
+    }
+  }
+
+  /// The value at sample `i` alone, reading only the samples it depends on.
+  /// Averages and totals over every sample still read those before it.
+  pub fn value_at<S, V, T>(
+    &self,
+    samples: &[(usize, S)],
+    i: usize,
+    value: V,
+    time: T,
+  ) -> f64
+  where
+    V: Fn(&S, TStatID) -> f64,
+    T: Fn(usize) -> f64,
+  {
+    let start = match &self.derivation {
+      Derivation::MovingAverage { window, .. } => {
+        (i + 1).saturating_sub(*window)
+      }
+      Derivation::Rate { id, .. } => (0..i)
+        .rev()
+        .find(|j| value(&samples[*j].1, id.clone()).is_finite())
+        .unwrap_or(i),
+      Derivation::Ratio { .. } => i,
+      Derivation::Ema { .. } | Derivation::CumulativeSum { .. } => 0,
+    };
+    self
+      .values(&samples[start..=i], &value, time)
+      .last()
+      .map_or(f64::NAN, |(_, value)| *value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    stats::{Statistics, StatisticsGroup, StatisticsTrackingSimulator},
+    Simulation,
+  };
+
+  /// Counts up by the tick number, so the count grows by `tick` each tick.
+  struct Counter;
+
+  impl Simulation for Counter {
+    type TState = (usize, f64);
+
+    fn tick(&mut self, state: &mut (usize, f64)) {
+      state.0 += 1;
+      state.1 += state.0 as f64;
+    }
+  }
+
+  struct Stats {
+    count: f64,
+  }
+
+  impl Statistics<(usize, f64)> for Stats {
+    type TStatID = &'static str;
+
+    fn get_tick_unit() -> String {
+      "tick".into()
+    }
+
+    fn map_tick_unit(tick: usize) -> f64 {
+      tick as f64
+    }
+
+    fn get_groups() -> Vec<StatisticsGroup<(usize, f64), Self>> {
+      vec![StatisticsGroup::new("Count", "", vec!["count"])
+        .record_every(3)
+        .derived(DerivedSeries::rate("count", 1.0))]
+    }
+
+    fn get_value(&self, _name: &'static str) -> f64 {
+      self.count
+    }
+
+    fn derive(state: &(usize, f64)) -> Self {
+      Self { count: state.1 }
+    }
+
+    fn derive_groups(state: &(usize, f64), due: &[bool]) -> Self {
+      match due[0] {
+        true => Self::derive(state),
+        false => Self { count: f64::NAN },
+      }
+    }
+  }
+
+  #[test]
+  fn rate_spans_samples_of_groups_recorded_less_often() {
+    let mut simulator =
+      StatisticsTrackingSimulator::<_, Stats>::new(Counter, (0, 0.0));
+    simulator.run(9);
+    let samples = &simulator.stats.statistics;
+    let group = &Stats::get_groups()[0];
+    let rates = &group.derived_values(samples)[0];
+
+    // The count is tick * (tick + 1) / 2, derived on ticks 0, 3, 6 and 9
+    let expected = |tick: usize| match tick {
+      3 => 2.0,
+      6 => 5.0,
+      9 => 8.0,
+      _ => f64::NAN,
+    };
+    for (i, (tick, rate)) in rates.iter().enumerate() {
+      let at = group.derived_values_at(samples, i)[0];
+      if expected(*tick).is_nan() {
+        assert!(rate.is_nan() && at.is_nan(), "tick {}", tick);
+      } else {
+        assert_eq!((*rate, at), (expected(*tick), expected(*tick)));
+      }
+    }
+    assert_eq!(rates.len(), 10);
+  }
+}
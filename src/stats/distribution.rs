@@ -0,0 +1,203 @@
+use super::{source::timestamp, Statistics, TimeAxis, Unit};
+use std::{io::Write, marker::PhantomData, ops::Range};
+
+/// Counts of values in equal width bins between `min` and `max`. Values
+/// outside the range are counted in the first or last bin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+  pub min: f64,
+  pub max: f64,
+  pub counts: Vec<f64>,
+}
+
+impl Histogram {
+  pub fn new(min: f64, max: f64, bins: usize) -> Self {
+    Self {
+      min,
+      max,
+      counts: vec![0.0; bins.max(1)],
+    }
+  }
+
+  pub fn from_values<I>(min: f64, max: f64, bins: usize, values: I) -> Self
+  where
+    I: IntoIterator<Item = f64>,
+  {
+    let mut histogram = Self::new(min, max, bins);
+    for value in values {
+      histogram.add(value);
+    }
+    histogram
+  }
+
+  /// Adds a value. NaN values are ignored.
+  pub fn add(&mut self, value: f64) {
+    self.add_weighted(value, 1.0);
+  }
+
+  pub fn add_weighted(&mut self, value: f64, weight: f64) {
+    if value.is_nan() {
+      return;
+    }
+    let bin = self.bin_of(value);
+    self.counts[bin] += weight;
+  }
+
+  /// The bin a value is counted in.
+  pub fn bin_of(&self, value: f64) -> usize {
+    let bins = self.counts.len();
+    let t = (value - self.min) / (self.max - self.min);
+    ((t * bins as f64).floor().max(0.0) as usize).min(bins - 1)
+  }
+
+  pub fn bins(&self) -> usize {
+    self.counts.len()
+  }
+
+  pub fn bin_width(&self) -> f64 {
+    (self.max - self.min) / self.counts.len() as f64
+  }
+
+  pub fn bin_range(&self, bin: usize) -> Range<f64> {
+    let width = self.bin_width();
+    self.min + bin as f64 * width..self.min + (bin + 1) as f64 * width
+  }
+
+  pub fn total(&self) -> f64 {
+    self.counts.iter().sum()
+  }
+
+  /// Estimates a quantile, assuming values are spread evenly within bins.
+  pub fn quantile(&self, q: f64) -> f64 {
+    let total = self.total();
+    if total <= 0.0 {
+      return f64::NAN;
+    }
+    let target = q.clamp(0.0, 1.0) * total;
+    let mut cumulative = 0.0;
+    for (bin, count) in self.counts.iter().enumerate() {
+      if *count > 0.0 && cumulative + count >= target {
+        let range = self.bin_range(bin);
+        let t = (target - cumulative) / count;
+        return range.start + t * (range.end - range.start);
+      }
+      cumulative += count;
+    }
+    self.max
+  }
+}
+
+/// How a distribution group is charted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DistributionView {
+  /// The histogram of the most recent sample in view.
+  #[default]
+  Histogram,
+  /// Every sample's histogram, as a tick by bin heatmap.
+  Heatmap,
+  /// Bands between percentiles over time.
+  PercentileFan,
+}
+
+impl DistributionView {
+  pub fn next(self) -> Self {
+    match self {
+      DistributionView::Histogram => DistributionView::Heatmap,
+      DistributionView::Heatmap => DistributionView::PercentileFan,
+      DistributionView::PercentileFan => DistributionView::Histogram,
+    }
+  }
+}
+
+/// A distribution-valued statistic, charted on its own.
+pub struct DistributionGroup<TState, TStatistics: Statistics<TState>> {
+  pub title: String,
+  pub unit: Unit,
+  /// The unit values are shown in, if not `unit`.
+  pub display_unit: Option<Unit>,
+  pub name: TStatistics::TStatID,
+  pub view: DistributionView,
+  _state: PhantomData<TState>,
+  _statistics: PhantomData<TStatistics>,
+}
+
+impl<TState, TStatistics: Statistics<TState>>
+  DistributionGroup<TState, TStatistics>
+{
+  pub fn new(
+    title: &str,
+    unit: impl Into<Unit>,
+    name: TStatistics::TStatID,
+  ) -> Self {
+    Self {
+      title: title.into(),
+      unit: unit.into(),
+      display_unit: None,
+      name,
+      view: DistributionView::default(),
+      _state: PhantomData,
+      _statistics: PhantomData,
+    }
+  }
+
+  pub fn view(mut self, view: DistributionView) -> Self {
+    self.view = view;
+    self
+  }
+
+  /// Shows values in another unit, as `StatisticsGroup::display_unit`.
+  pub fn display_unit(mut self, unit: Unit) -> Self {
+    self.display_unit = Some(unit);
+    self
+  }
+
+  /// The unit values are shown in.
+  pub fn shown_unit(&self) -> &Unit {
+    self
+      .display_unit
+      .as_ref()
+      .filter(|unit| self.unit.converts_to(unit))
+      .unwrap_or(&self.unit)
+  }
+
+  /// A recorded value in the unit it is shown in.
+  pub fn shown_value(&self, value: f64) -> f64 {
+    self.unit.convert(value, self.shown_unit()).unwrap_or(value)
+  }
+
+  pub fn get_histogram(&self, stats: &TStatistics) -> Histogram {
+    stats
+      .get_distribution(self.name.clone())
+      .unwrap_or_else(|| Histogram::new(0.0, 1.0, 1))
+  }
+
+  /// Writes every sample's bins as `tick,bin_start,bin_end,count` rows.
+  /// Statistics on calendar time axes add an ISO 8601 `timestamp` column
+  /// after `tick`.
+  pub fn write_csv<W: Write>(
+    &self,
+    samples: &[(usize, TStatistics)],
+    mut writer: W,
+  ) -> std::io::Result<()> {
+    let calendar =
+      matches!(TStatistics::get_time_axis(), TimeAxis::Calendar(_));
+    if calendar {
+      writeln!(writer, "tick,timestamp,bin_start,bin_end,count")?;
+    } else {
+      writeln!(writer, "tick,bin_start,bin_end,count")?;
+    }
+    for (tick, stats) in samples {
+      let when = match timestamp::<TState, TStatistics>(*tick) {
+        Some(timestamp) => format!("{},{}", tick, timestamp),
+        None if calendar => format!("{},", tick),
+        None => tick.to_string(),
+      };
+      let histogram = self.get_histogram(stats);
+      for (bin, count) in histogram.counts.iter().enumerate() {
+        let range = histogram.bin_range(bin);
+        writeln!(writer, "{},{},{},{}", when, range.start, range.end, count)?;
+      }
+    }
+    Ok(())
+  }
+}
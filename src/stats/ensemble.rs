@@ -0,0 +1,320 @@
+use super::{
+  convergence::moments,
+  hypothesis::{kolmogorov_smirnov, mann_whitney, welch_t, TestResult},
+  summary::Welford,
+  StatisticsSource,
+};
+use std::{
+  fmt::{self, Display},
+  io::Write,
+};
+
+/// What one run contributes to its ensemble's sample of a series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Measure {
+  /// The value of the last sample at or before a tick.
+  Tick(usize),
+  /// The value of the last sample.
+  Final,
+  /// The time average over every sample.
+  Mean,
+  Min,
+  Max,
+}
+
+impl Measure {
+  /// The measure of one series of a run, NaN when it has no finite value.
+  pub fn of(
+    &self,
+    source: &dyn StatisticsSource,
+    group: usize,
+    stat: usize,
+  ) -> f64 {
+    let values = || {
+      (0..source.len())
+        .map(move |sample| source.value(sample, group, stat))
+        .filter(|value| value.is_finite())
+    };
+    match self {
+      Measure::Tick(tick) => (0..source.len())
+        .take_while(|sample| source.tick(*sample) <= *tick)
+        .map(|sample| source.value(sample, group, stat))
+        .filter(|value| value.is_finite())
+        .last()
+        .unwrap_or(f64::NAN),
+      Measure::Final => values().last().unwrap_or(f64::NAN),
+      Measure::Mean => moments(&values().collect::<Vec<_>>()).mean(),
+      Measure::Min => values().fold(f64::NAN, f64::min),
+      Measure::Max => values().fold(f64::NAN, f64::max),
+    }
+  }
+}
+
+impl Display for Measure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Measure::Tick(tick) => write!(f, "tick {}", tick),
+      Measure::Final => write!(f, "final"),
+      Measure::Mean => write!(f, "mean"),
+      Measure::Min => write!(f, "min"),
+      Measure::Max => write!(f, "max"),
+    }
+  }
+}
+
+/// Compares the distributions of two ensembles of runs, such as runs of a
+/// model before and after a change, series by series. Series are matched by
+/// group title and stat label, so the ensembles may come from different
+/// statistics types.
+pub struct EnsembleComparison<'a> {
+  a: &'a [&'a dyn StatisticsSource],
+  b: &'a [&'a dyn StatisticsSource],
+  measures: Vec<Measure>,
+  alpha: f64,
+  bonferroni: bool,
+}
+
+impl<'a> EnsembleComparison<'a> {
+  pub fn new(
+    a: &'a [&'a dyn StatisticsSource],
+    b: &'a [&'a dyn StatisticsSource],
+  ) -> Self {
+    Self {
+      a,
+      b,
+      measures: vec![Measure::Final],
+      alpha: 0.05,
+      bonferroni: false,
+    }
+  }
+
+  /// What to compare for every series. Defaults to the final values.
+  pub fn measures(mut self, measures: Vec<Measure>) -> Self {
+    self.measures = measures;
+    self
+  }
+
+  /// The significance level of each test. Defaults to 0.05.
+  pub fn alpha(mut self, alpha: f64) -> Self {
+    self.alpha = alpha;
+    self
+  }
+
+  /// Divides `alpha` by the number of comparisons, so that many series
+  /// don't flag differences by chance alone.
+  pub fn bonferroni(mut self, bonferroni: bool) -> Self {
+    self.bonferroni = bonferroni;
+    self
+  }
+
+  pub fn compare(&self) -> EnsembleReport {
+    let labels = |ensemble: &[&dyn StatisticsSource]| -> Vec<String> {
+      ensemble
+        .first()
+        .map_or(vec![], |source| series_labels(*source))
+    };
+    let (labels_a, labels_b) = (labels(self.a), labels(self.b));
+    let unmatched = labels_a
+      .iter()
+      .filter(|label| !labels_b.contains(label))
+      .chain(labels_b.iter().filter(|label| !labels_a.contains(label)))
+      .cloned()
+      .collect();
+
+    let mut comparisons = vec![];
+    for series in labels_a.iter().filter(|label| labels_b.contains(label)) {
+      for measure in self.measures.iter() {
+        let a = sample(self.a, series, measure);
+        let b = sample(self.b, series, measure);
+        comparisons.push(SeriesComparison {
+          series: series.clone(),
+          measure: *measure,
+          a: moments(&a),
+          b: moments(&b),
+          kolmogorov_smirnov: kolmogorov_smirnov(&a, &b),
+          mann_whitney: mann_whitney(&a, &b),
+          welch_t: welch_t(&a, &b),
+        });
+      }
+    }
+
+    let alpha = if self.bonferroni && !comparisons.is_empty() {
+      self.alpha / (comparisons.len() * TESTS) as f64
+    } else {
+      self.alpha
+    };
+    EnsembleReport {
+      alpha,
+      comparisons,
+      unmatched,
+    }
+  }
+}
+
+/// How many tests each comparison runs.
+const TESTS: usize = 3;
+
+fn series_labels(source: &dyn StatisticsSource) -> Vec<String> {
+  source
+    .groups()
+    .iter()
+    .flat_map(|group| {
+      group
+        .stats
+        .iter()
+        .map(move |stat| format!("{}/{}", group.title, stat.label))
+    })
+    .collect()
+}
+
+/// The measure of a series over an ensemble, leaving out runs that lack the
+/// series or a finite value of it.
+fn sample(
+  ensemble: &[&dyn StatisticsSource],
+  series: &str,
+  measure: &Measure,
+) -> Vec<f64> {
+  ensemble
+    .iter()
+    .filter_map(|source| {
+      let groups = source.groups();
+      groups.iter().enumerate().find_map(|(g, group)| {
+        group
+          .stats
+          .iter()
+          .position(|stat| format!("{}/{}", group.title, stat.label) == series)
+          .map(|s| measure.of(*source, g, s))
+      })
+    })
+    .filter(|value| value.is_finite())
+    .collect()
+}
+
+/// The tests of one measure of one series.
+#[derive(Clone, Debug)]
+pub struct SeriesComparison {
+  /// The series, labelled `group/stat`.
+  pub series: String,
+  pub measure: Measure,
+  pub a: Welford,
+  pub b: Welford,
+  pub kolmogorov_smirnov: TestResult,
+  pub mann_whitney: TestResult,
+  pub welch_t: TestResult,
+}
+
+impl SeriesComparison {
+  /// Whether any of the tests finds a difference.
+  pub fn significant(&self, alpha: f64) -> bool {
+    self.kolmogorov_smirnov.significant(alpha)
+      || self.mann_whitney.significant(alpha)
+      || self.welch_t.significant(alpha)
+  }
+
+  fn tests(&self) -> [TestResult; TESTS] {
+    [self.kolmogorov_smirnov, self.mann_whitney, self.welch_t]
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnsembleReport {
+  /// The significance level of each test, after any correction.
+  pub alpha: f64,
+  pub comparisons: Vec<SeriesComparison>,
+  /// Series found in only one of the ensembles.
+  pub unmatched: Vec<String>,
+}
+
+impl EnsembleReport {
+  pub fn significant(&self) -> impl Iterator<Item = &SeriesComparison> {
+    self
+      .comparisons
+      .iter()
+      .filter(move |comparison| comparison.significant(self.alpha))
+  }
+
+  /// Writes a row per comparison, with each test's statistic, p-value and
+  /// effect size.
+  pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+    write!(writer, "series,measure,n_a,mean_a,sd_a,n_b,mean_b,sd_b")?;
+    for test in ["ks", "mann_whitney", "welch_t"].iter() {
+      write!(writer, ",{0}_statistic,{0}_p,{0}_effect", test)?;
+    }
+    writeln!(writer, ",significant")?;
+    for comparison in self.comparisons.iter() {
+      write!(
+        writer,
+        "\"{}\",{}",
+        comparison.series.replace('"', "\"\""),
+        comparison.measure
+      )?;
+      for moments in [&comparison.a, &comparison.b].iter() {
+        write!(
+          writer,
+          ",{},{},{}",
+          moments.count(),
+          moments.mean(),
+          moments.variance().sqrt()
+        )?;
+      }
+      for test in comparison.tests().iter() {
+        write!(
+          writer,
+          ",{},{},{}",
+          test.statistic, test.p_value, test.effect_size
+        )?;
+      }
+      writeln!(writer, ",{}", comparison.significant(self.alpha))?;
+    }
+    Ok(())
+  }
+}
+
+impl Display for EnsembleReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let flagged = self.significant().count();
+    // Enough digits to show a corrected alpha to two significant figures
+    let digits = (-self.alpha.log10()).ceil().max(0.0) as usize + 1;
+    writeln!(
+      f,
+      "{} of {} comparisons differ at alpha {:.*}",
+      flagged,
+      self.comparisons.len(),
+      digits,
+      self.alpha
+    )?;
+    for comparison in self.comparisons.iter() {
+      let marker = if comparison.significant(self.alpha) {
+        "*"
+      } else {
+        " "
+      };
+      let (a, b) = (&comparison.a, &comparison.b);
+      writeln!(
+        f,
+        "{} {} ({}): {:.4} ± {:.4} (n {}) vs {:.4} ± {:.4} (n {})",
+        marker,
+        comparison.series,
+        comparison.measure,
+        a.mean(),
+        a.variance().sqrt(),
+        a.count(),
+        b.mean(),
+        b.variance().sqrt(),
+        b.count()
+      )?;
+      let tests: Vec<_> = ["KS D", "Mann-Whitney r", "Welch d"]
+        .iter()
+        .zip(comparison.tests().iter())
+        .map(|(name, test)| {
+          format!("{} {:.3}, p {:.4}", name, test.effect_size, test.p_value)
+        })
+        .collect();
+      writeln!(f, "    {}", tests.join("; "))?;
+    }
+    for series in self.unmatched.iter() {
+      writeln!(f, "  {}: in only one ensemble", series)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,441 @@
+use super::convergence::moments;
+use std::cmp::Ordering;
+
+/// The outcome of a two-sample test. The p-value is NaN when either sample
+/// is too small for the test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TestResult {
+  pub statistic: f64,
+  /// Two-sided.
+  pub p_value: f64,
+  pub effect_size: f64,
+}
+
+impl TestResult {
+  fn undefined() -> Self {
+    Self {
+      statistic: f64::NAN,
+      p_value: f64::NAN,
+      effect_size: f64::NAN,
+    }
+  }
+
+  pub fn significant(&self, alpha: f64) -> bool {
+    self.p_value < alpha
+  }
+}
+
+/// The two-sample Kolmogorov-Smirnov test. The statistic and effect size
+/// are both D, the largest gap between the empirical distributions, and the
+/// p-value is asymptotic.
+pub fn kolmogorov_smirnov(a: &[f64], b: &[f64]) -> TestResult {
+  if a.is_empty() || b.is_empty() {
+    return TestResult::undefined();
+  }
+  let (a, b) = (sorted(a), sorted(b));
+  let (n, m) = (a.len() as f64, b.len() as f64);
+  let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+  while i < a.len() && j < b.len() {
+    let x = a[i].min(b[j]);
+    while i < a.len() && a[i] <= x {
+      i += 1;
+    }
+    while j < b.len() && b[j] <= x {
+      j += 1;
+    }
+    d = d.max((i as f64 / n - j as f64 / m).abs());
+  }
+
+  let effective = (n * m / (n + m)).sqrt();
+  let lambda = (effective + 0.12 + 0.11 / effective) * d;
+  TestResult {
+    statistic: d,
+    p_value: kolmogorov_q(lambda),
+    effect_size: d,
+  }
+}
+
+/// The Mann-Whitney U test, with the normal approximation corrected for
+/// ties. The statistic is U of `b`, and the effect size is the rank-biserial
+/// correlation, positive when `b` tends to be larger.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> TestResult {
+  if a.is_empty() || b.is_empty() {
+    return TestResult::undefined();
+  }
+  let mut values: Vec<(f64, bool)> = a
+    .iter()
+    .map(|value| (*value, false))
+    .chain(b.iter().map(|value| (*value, true)))
+    .collect();
+  values.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal));
+
+  let total = values.len() as f64;
+  let (mut rank_b, mut ties) = (0.0, 0.0);
+  let mut i = 0;
+  while i < values.len() {
+    let tied = values[i..]
+      .iter()
+      .take_while(|v| v.0 == values[i].0)
+      .count();
+    // Tied values share the mean of their ranks, which start at 1
+    let rank = i as f64 + (tied as f64 + 1.0) / 2.0;
+    rank_b += rank * values[i..i + tied].iter().filter(|v| v.1).count() as f64;
+    ties += (tied as f64).powi(3) - tied as f64;
+    i += tied;
+  }
+
+  let (n, m) = (a.len() as f64, b.len() as f64);
+  let u = rank_b - m * (m + 1.0) / 2.0;
+  let mean = n * m / 2.0;
+  let variance =
+    n * m / 12.0 * ((total + 1.0) - ties / (total * (total - 1.0)));
+  let p_value = if variance > 0.0 {
+    let difference = (u - mean).abs();
+    // Continuity correction
+    let z = (difference - 0.5).max(0.0) / variance.sqrt();
+    normal_two_sided(z)
+  } else {
+    1.0
+  };
+  TestResult {
+    statistic: u,
+    p_value,
+    effect_size: 2.0 * u / (n * m) - 1.0,
+  }
+}
+
+/// Welch's t-test for a difference in means, which doesn't assume equal
+/// variances. The statistic is t of `b` minus `a`, and the effect size is
+/// Cohen's d with the pooled standard deviation.
+pub fn welch_t(a: &[f64], b: &[f64]) -> TestResult {
+  if a.len() < 2 || b.len() < 2 {
+    return TestResult::undefined();
+  }
+  let (a, b) = (moments(a), moments(b));
+  let (n, m) = (a.count() as f64, b.count() as f64);
+  let difference = b.mean() - a.mean();
+  let (error_a, error_b) = (a.variance() / n, b.variance() / m);
+  let error = (error_a + error_b).sqrt();
+  let pooled = (((n - 1.0) * a.variance() + (m - 1.0) * b.variance())
+    / (n + m - 2.0))
+    .sqrt();
+
+  // Constant samples differ certainly, or not at all
+  if error == 0.0 {
+    let differ = difference != 0.0;
+    return TestResult {
+      statistic: if differ {
+        difference * f64::INFINITY
+      } else {
+        0.0
+      },
+      p_value: if differ { 0.0 } else { 1.0 },
+      effect_size: if differ {
+        difference * f64::INFINITY
+      } else {
+        0.0
+      },
+    };
+  }
+  let t = difference / error;
+  let df = (error_a + error_b).powi(2)
+    / (error_a.powi(2) / (n - 1.0) + error_b.powi(2) / (m - 1.0));
+  TestResult {
+    statistic: t,
+    p_value: incomplete_beta(df / 2.0, 0.5, df / (df + t * t)),
+    effect_size: difference / pooled,
+  }
+}
+
+fn sorted(values: &[f64]) -> Vec<f64> {
+  let mut values = values.to_vec();
+  values.sort_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+  values
+}
+
+/// The probability of a Kolmogorov distributed variable exceeding `lambda`.
+fn kolmogorov_q(lambda: f64) -> f64 {
+  if lambda < 0.2 {
+    return 1.0;
+  }
+  let mut sum = 0.0;
+  for k in 1..=100 {
+    let k = k as f64;
+    let term = (-2.0 * k * k * lambda * lambda).exp();
+    sum += if k % 2.0 == 1.0 { term } else { -term };
+    if term < 1e-12 {
+      break;
+    }
+  }
+  (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// The probability of a standard normal variable being further than `z`
+/// from zero.
+fn normal_two_sided(z: f64) -> f64 {
+  erfc(z.abs() / std::f64::consts::SQRT_2)
+}
+
+/// The complementary error function, to within 1.2e-7.
+fn erfc(x: f64) -> f64 {
+  let z = x.abs();
+  let t = 1.0 / (1.0 + 0.5 * z);
+  let r = t
+    * (-z * z - 1.26551223
+      + t
+        * (1.00002368
+          + t
+            * (0.37409196
+              + t
+                * (0.09678418
+                  + t
+                    * (-0.18628806
+                      + t
+                        * (0.27886807
+                          + t
+                            * (-1.13520398
+                              + t
+                                * (1.48851587
+                                  + t * (-0.82215223 + t * 0.17087277)))))))))
+      .exp();
+  if x >= 0.0 {
+    r
+  } else {
+    2.0 - r
+  }
+}
+
+/// The natural log of the gamma function, by Lanczos' approximation.
+fn ln_gamma(x: f64) -> f64 {
+  const COEFFICIENTS: [f64; 6] = [
+    76.18009172947146,
+    -86.50532032941677,
+    24.01409824083091,
+    -1.231739572450155,
+    0.1208650973866179e-2,
+    -0.5395239384953e-5,
+  ];
+  let tmp = x + 5.5;
+  let tmp = tmp - (x + 0.5) * tmp.ln();
+  let mut series = 1.000000000190015;
+  for (i, coefficient) in COEFFICIENTS.iter().enumerate() {
+    series += coefficient / (x + 1.0 + i as f64);
+  }
+  -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// The regularized incomplete beta function I_x(a, b).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+  if x <= 0.0 {
+    return 0.0;
+  }
+  if x >= 1.0 {
+    return 1.0;
+  }
+  let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b)
+    + a * x.ln()
+    + b * (1.0 - x).ln())
+  .exp();
+  // The continued fraction converges quickly on this side of the mean
+  if x < (a + 1.0) / (a + b + 2.0) {
+    front * beta_fraction(a, b, x) / a
+  } else {
+    1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+  }
+}
+
+/// The continued fraction of the incomplete beta function, by Lentz's method.
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+  const TINY: f64 = 1e-300;
+  let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+  let mut c = 1.0;
+  let mut d = 1.0 - qab * x / qap;
+  if d.abs() < TINY {
+    d = TINY;
+  }
+  d = 1.0 / d;
+  let mut h = d;
+  for m in 1..=300 {
+    let m = m as f64;
+    let m2 = 2.0 * m;
+    let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+    d = 1.0 + aa * d;
+    if d.abs() < TINY {
+      d = TINY;
+    }
+    c = 1.0 + aa / c;
+    if c.abs() < TINY {
+      c = TINY;
+    }
+    d = 1.0 / d;
+    h *= d * c;
+    let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+    d = 1.0 + aa * d;
+    if d.abs() < TINY {
+      d = TINY;
+    }
+    c = 1.0 + aa / c;
+    if c.abs() < TINY {
+      c = TINY;
+    }
+    d = 1.0 / d;
+    let delta = d * c;
+    h *= delta;
+    if (delta - 1.0).abs() < 1e-12 {
+      break;
+    }
+  }
+  h
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+      (actual - expected).abs() <= tolerance,
+      "{} is not within {:e} of {}",
+      actual,
+      tolerance,
+      expected
+    );
+  }
+
+  #[test]
+  fn erfc_matches_reference() {
+    for (x, expected) in [
+      (0.0, 1.0),
+      (0.5, 0.4795001221869535),
+      (1.0, 0.15729920705028513),
+      (2.0, 0.004677734981047265),
+      (-1.0, 1.842700792949715),
+    ] {
+      assert_close(erfc(x), expected, 1.2e-7);
+    }
+  }
+
+  #[test]
+  fn ln_gamma_matches_reference() {
+    for (x, expected) in [
+      (0.1, 2.2527126517342055),
+      (0.5, 0.5723649429247004),
+      (1.0, 0.0),
+      (5.0, 24f64.ln()),
+      (10.3, 13.48203678613836),
+    ] {
+      assert_close(ln_gamma(x), expected, 1e-10);
+    }
+  }
+
+  #[test]
+  fn incomplete_beta_matches_reference() {
+    for (a, b, x, expected) in [
+      // P(at least 2 successes in 4 trials of 0.4)
+      (2.0, 3.0, 0.4, 0.5248),
+      // The arcsine distribution, 2 / pi * asin(sqrt(x))
+      (0.5, 0.5, 0.25, 1.0 / 3.0),
+      (5.0, 0.5, 0.9, 0.3166429150200123),
+      (10.0, 20.0, 0.3, 0.3640040810719442),
+    ] {
+      assert_close(incomplete_beta(a, b, x), expected, 1e-10);
+    }
+    assert_eq!(incomplete_beta(2.0, 3.0, 0.0), 0.0);
+    assert_eq!(incomplete_beta(2.0, 3.0, 1.0), 1.0);
+  }
+
+  #[test]
+  fn kolmogorov_smirnov_matches_reference() {
+    // The statistic agrees with scipy.stats.ks_2samp. The p-values are of
+    // the asymptotic series with Stephens' correction, summed with mpmath,
+    // rather than scipy's exact small-sample ones.
+    let test = kolmogorov_smirnov(
+      &[1.0, 2.0, 3.0, 4.0, 5.0],
+      &[3.0, 4.0, 5.0, 6.0, 7.0, 8.0],
+    );
+    assert_eq!(test.statistic, 0.5);
+    assert_close(test.p_value, 0.3670013850902252, 1e-9);
+
+    let test = kolmogorov_smirnov(
+      &[0.1, 0.4, 0.7, 1.2, 1.5, 2.2, 2.9, 3.1],
+      &[1.8, 2.5, 3.3, 3.9, 4.4, 5.0, 5.6, 6.1],
+    );
+    assert_eq!(test.statistic, 0.75);
+    assert_close(test.p_value, 0.009766412646232907, 1e-9);
+
+    let same = kolmogorov_smirnov(&[1.0, 2.0, 2.0], &[2.0, 1.0, 2.0]);
+    assert_eq!((same.statistic, same.p_value), (0.0, 1.0));
+    assert!(kolmogorov_smirnov(&[], &[1.0]).p_value.is_nan());
+  }
+
+  #[test]
+  fn mann_whitney_matches_reference() {
+    // scipy.stats.mannwhitneyu(males, females, method="asymptotic") gives
+    // U 17 of the males, so U 3 of the females
+    let test =
+      mann_whitney(&[19.0, 22.0, 16.0, 29.0, 24.0], &[20.0, 11.0, 17.0, 12.0]);
+    assert_eq!(test.statistic, 3.0);
+    assert_close(test.p_value, 0.11134688653314041, 1e-7);
+    assert_close(test.effect_size, -0.7, 1e-12);
+
+    let test =
+      mann_whitney(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]);
+    assert_eq!((test.statistic, test.effect_size), (25.0, 1.0));
+    assert_close(test.p_value, 0.012185780355344813, 1e-7);
+  }
+
+  #[test]
+  fn mann_whitney_corrects_for_ties() {
+    let test =
+      mann_whitney(&[1.0, 2.0, 2.0, 3.0, 3.0, 3.0], &[2.0, 3.0, 4.0, 4.0, 5.0]);
+    assert_eq!(test.statistic, 24.5);
+    assert_close(test.p_value, 0.0887136919967762, 1e-7);
+    assert_close(test.effect_size, 0.6333333333333333, 1e-12);
+
+    let all_tied = mann_whitney(&[1.0, 1.0], &[1.0, 1.0, 1.0]);
+    assert_eq!((all_tied.statistic, all_tied.p_value), (3.0, 1.0));
+  }
+
+  #[test]
+  fn welch_t_matches_reference() {
+    // The first example of the Wikipedia article on Welch's t-test
+    let test = welch_t(
+      &[
+        27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6,
+        19.0, 21.7, 21.4,
+      ],
+      &[
+        27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1,
+        22.9, 20.5, 24.4,
+      ],
+    );
+    assert_close(test.statistic, 2.45535639828601, 1e-10);
+    assert_close(test.p_value, 0.021378001462866816, 1e-8);
+    assert_close(test.effect_size, 0.8965693907039245, 1e-10);
+
+    let test = welch_t(
+      &[1.0, 2.0, 3.0, 4.0, 5.0],
+      &[2.0, 4.0, 6.0, 8.0, 10.0, 12.0],
+    );
+    assert_close(test.statistic, 2.3763541031440183, 1e-10);
+    assert_close(test.p_value, 0.04928433820673054, 1e-8);
+    assert_close(test.effect_size, 1.3416407864998738, 1e-10);
+  }
+
+  #[test]
+  fn welch_t_on_constant_samples() {
+    let same = welch_t(&[2.0, 2.0, 2.0], &[2.0, 2.0]);
+    assert_eq!(
+      (same.statistic, same.p_value, same.effect_size),
+      (0.0, 1.0, 0.0)
+    );
+
+    let higher = welch_t(&[1.0, 1.0, 1.0], &[2.0, 2.0]);
+    assert_eq!((higher.statistic, higher.p_value), (f64::INFINITY, 0.0));
+    assert_eq!(higher.effect_size, f64::INFINITY);
+    let lower = welch_t(&[2.0, 2.0], &[1.0, 1.0, 1.0]);
+    assert_eq!(lower.statistic, f64::NEG_INFINITY);
+
+    assert!(welch_t(&[1.0], &[1.0, 2.0]).p_value.is_nan());
+  }
+}
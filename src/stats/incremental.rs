@@ -0,0 +1,69 @@
+use super::Statistics;
+use crate::{Simulation, Simulator};
+
+/// Statistics kept up to date from what changed each tick, rather than
+/// derived from the whole state every time a sample is taken.
+pub trait IncrementalStatistics<T>: Statistics<T> + Clone {
+  type TChange;
+
+  /// Brings the statistics up to date with `state`, given the changes the
+  /// simulation reported in the last tick. Statistics that only need their
+  /// previous value and the state can use `()` changes and ignore them.
+  fn update(&mut self, state: &T, changes: &[Self::TChange]);
+}
+
+/// A simulation reporting what changed in each tick, for incremental
+/// statistics. Called after every tick.
+pub trait ChangeEvents<TChange> {
+  fn changes(&mut self) -> Vec<TChange>;
+}
+
+impl<TSimulation: Simulation> ChangeEvents<()> for TSimulation {
+  fn changes(&mut self) -> Vec<()> {
+    vec![]
+  }
+}
+
+fn update<TSimulation, TStatistics>(
+  simulator: &mut Simulator<TSimulation>,
+  stats: &mut TStatistics,
+) where
+  TSimulation: Simulation + ChangeEvents<TStatistics::TChange>,
+  TStatistics: IncrementalStatistics<TSimulation::TState>,
+{
+  let changes = simulator.changes();
+  stats.update(simulator.state(), &changes);
+}
+
+/// The running statistics of a tracker, updated every tick.
+pub(crate) struct Incremental<TSimulation: Simulation, TStatistics> {
+  current: TStatistics,
+  update: fn(&mut Simulator<TSimulation>, &mut TStatistics),
+  clone: fn(&TStatistics) -> TStatistics,
+}
+
+impl<TSimulation, TStatistics> Incremental<TSimulation, TStatistics>
+where
+  TSimulation: Simulation + ChangeEvents<TStatistics::TChange>,
+  TStatistics: IncrementalStatistics<TSimulation::TState>,
+{
+  pub fn new(state: &TSimulation::TState) -> Self {
+    Self {
+      current: TStatistics::derive(state),
+      update: update::<TSimulation, TStatistics>,
+      clone: TStatistics::clone,
+    }
+  }
+}
+
+impl<TSimulation: Simulation, TStatistics>
+  Incremental<TSimulation, TStatistics>
+{
+  pub fn update(&mut self, simulator: &mut Simulator<TSimulation>) {
+    (self.update)(simulator, &mut self.current);
+  }
+
+  pub fn current(&self) -> TStatistics {
+    (self.clone)(&self.current)
+  }
+}
@@ -0,0 +1,61 @@
+use super::Statistics;
+use std::marker::PhantomData;
+
+/// How time is shown along a phase plot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PhaseColoring {
+  /// Every point in view, coloured from the earliest tick to the latest.
+  #[default]
+  Gradient,
+  /// Only the most recent points, fading out with age.
+  Trail,
+}
+
+impl PhaseColoring {
+  pub fn next(self) -> Self {
+    match self {
+      PhaseColoring::Gradient => PhaseColoring::Trail,
+      PhaseColoring::Trail => PhaseColoring::Gradient,
+    }
+  }
+}
+
+/// One statistic plotted against another, rather than against time.
+pub struct PhaseGroup<TState, TStatistics: Statistics<TState>> {
+  pub title: String,
+  pub x: TStatistics::TStatID,
+  pub y: TStatistics::TStatID,
+  pub coloring: PhaseColoring,
+  /// How many samples a trail keeps.
+  pub trail: usize,
+  _state: PhantomData<TState>,
+  _statistics: PhantomData<TStatistics>,
+}
+
+impl<TState, TStatistics: Statistics<TState>> PhaseGroup<TState, TStatistics> {
+  pub fn new(
+    title: &str,
+    x: TStatistics::TStatID,
+    y: TStatistics::TStatID,
+  ) -> Self {
+    Self {
+      title: title.into(),
+      x,
+      y,
+      coloring: PhaseColoring::default(),
+      trail: 100,
+      _state: PhantomData,
+      _statistics: PhantomData,
+    }
+  }
+
+  pub fn coloring(mut self, coloring: PhaseColoring) -> Self {
+    self.coloring = coloring;
+    self
+  }
+
+  pub fn trail(mut self, trail: usize) -> Self {
+    self.trail = trail.max(2);
+    self
+  }
+}
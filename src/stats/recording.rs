@@ -0,0 +1,562 @@
+use super::{
+  Annotation, DateTime, DistributionDescriptor, DistributionView,
+  GroupDescriptor, Histogram, PhaseColoring, PhaseDescriptor, Prefixes,
+  RecordedStats, StatDescriptor, StatisticsSource, TimeAxis, Unit,
+};
+use std::{
+  fs::File,
+  io::{self, BufRead, BufReader, Write},
+  path::Path,
+};
+
+/// The first line of a recording, and the version of the format.
+const HEADER: &str = "simulate-stats\t1";
+
+/// Writes every sample of a source in the native format, which keeps the
+/// units, tick mapping and annotations that CSV exports leave out.
+pub fn write_recording<W: Write>(
+  source: &dyn StatisticsSource,
+  mut writer: W,
+) -> io::Result<()> {
+  writeln!(writer, "{}", HEADER)?;
+  writeln!(
+    writer,
+    "unit\t{}\t{}",
+    unit_fields(&source.time_unit()),
+    unit_fields(&source.display_time_unit())
+  )?;
+  let time_offset = source.map_tick(0);
+  writeln!(
+    writer,
+    "time\t{}\t{}",
+    time_offset,
+    source.map_tick(1) - time_offset
+  )?;
+  match source.time_axis() {
+    TimeAxis::Numeric => writeln!(writer, "axis\tnumeric")?,
+    TimeAxis::Duration => writeln!(writer, "axis\tduration")?,
+    TimeAxis::Calendar(start) => writeln!(writer, "axis\tcalendar\t{}", start)?,
+  }
+  for group in source.groups() {
+    write!(
+      writer,
+      "group\t{}\t{}\t{}",
+      escape(&group.title),
+      unit_fields(&group.unit),
+      unit_fields(&group.display_unit)
+    )?;
+    for stat in group.stats.iter() {
+      write!(writer, "\t{}", escape(&stat.label))?;
+    }
+    writeln!(writer)?;
+  }
+  for group in source.distribution_groups() {
+    let view = match group.view {
+      DistributionView::Histogram => "histogram",
+      DistributionView::Heatmap => "heatmap",
+      DistributionView::PercentileFan => "fan",
+    };
+    writeln!(
+      writer,
+      "distribution\t{}\t{}\t{}\t{}",
+      escape(&group.title),
+      unit_fields(&group.unit),
+      unit_fields(&group.display_unit),
+      view
+    )?;
+  }
+  for group in source.phase_groups() {
+    let coloring = match group.coloring {
+      PhaseColoring::Gradient => "gradient",
+      PhaseColoring::Trail => "trail",
+    };
+    writeln!(
+      writer,
+      "phase\t{}\t{}\t{}\t{}\t{}",
+      escape(&group.title),
+      escape(&group.x),
+      escape(&group.y),
+      coloring,
+      group.trail
+    )?;
+  }
+  for annotation in source.annotations() {
+    writeln!(
+      writer,
+      "annotation\t{}\t{}",
+      annotation.tick,
+      escape(&annotation.label)
+    )?;
+  }
+  for sample in 0..source.len() {
+    write!(writer, "sample\t{}", source.tick(sample))?;
+    for (g, group) in source.groups().iter().enumerate() {
+      for s in 0..group.stats.len() {
+        write!(writer, "\t{}", source.value(sample, g, s))?;
+      }
+    }
+    for g in 0..source.phase_groups().len() {
+      let (x, y) = source.phase_point(sample, g);
+      write!(writer, "\t{}\t{}", x, y)?;
+    }
+    writeln!(writer)?;
+    // Histograms follow their sample, one line per distribution group
+    for g in 0..source.distribution_groups().len() {
+      let histogram = source.histogram(sample, g);
+      write!(writer, "histogram\t{}\t{}", histogram.min, histogram.max)?;
+      for count in histogram.counts.iter() {
+        write!(writer, "\t{}", count)?;
+      }
+      writeln!(writer)?;
+    }
+  }
+  Ok(())
+}
+
+impl RecordedStats {
+  /// Reads a recording, in the native format or, for `.csv` files, as
+  /// written by `write_source_csv`.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+    if path.extension().is_some_and(|extension| extension == "csv") {
+      Self::read_csv(reader)
+    } else {
+      Self::read(reader)
+    }
+  }
+
+  /// Reads the native format written by `write_recording`.
+  pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+    Self::read_all(reader)?
+      .into_iter()
+      .next()
+      .ok_or_else(|| invalid("not a statistics recording"))
+  }
+
+  /// Reads every recording in a stream of them, such as the runs of an
+  /// ensemble written one after another.
+  pub fn read_all<R: BufRead>(reader: R) -> io::Result<Vec<Self>> {
+    let mut all: Vec<Self> = vec![];
+    for line in reader.lines() {
+      let line = line?;
+      if line == HEADER {
+        all.push(Self {
+          time_unit: Unit::new(""),
+          display_time_unit: Unit::new(""),
+          time_axis: TimeAxis::Numeric,
+          time_offset: 0.0,
+          time_per_tick: 1.0,
+          groups: vec![],
+          samples: vec![],
+          distribution_groups: vec![],
+          histograms: vec![],
+          phase_groups: vec![],
+          phase_points: vec![],
+          annotations: vec![],
+        });
+        continue;
+      }
+      let recorded = match all.last_mut() {
+        Some(recorded) => recorded,
+        None if line.is_empty() => continue,
+        None => return Err(invalid("not a statistics recording")),
+      };
+      let mut fields = line.split('\t');
+      match fields.next() {
+        Some("unit") => {
+          recorded.time_unit = read_unit(&mut fields)?;
+          recorded.display_time_unit = read_unit(&mut fields)?;
+        }
+        Some("time") => {
+          recorded.time_offset = parse(field(&mut fields)?)?;
+          recorded.time_per_tick = parse(field(&mut fields)?)?;
+        }
+        Some("axis") => {
+          recorded.time_axis = match field(&mut fields)? {
+            "numeric" => TimeAxis::Numeric,
+            "duration" => TimeAxis::Duration,
+            "calendar" => {
+              let start = field(&mut fields)?;
+              TimeAxis::Calendar(DateTime::parse_iso(start).ok_or_else(
+                || invalid(&format!("invalid ISO 8601 start `{}`", start)),
+              )?)
+            }
+            axis => {
+              return Err(invalid(&format!("unknown time axis `{}`", axis)))
+            }
+          }
+        }
+        Some("group") => {
+          let title = unescape(field(&mut fields)?);
+          let (unit, display_unit) =
+            (read_unit(&mut fields)?, read_unit(&mut fields)?);
+          recorded.groups.push(GroupDescriptor {
+            title,
+            unit,
+            display_unit,
+            stats: fields
+              .map(|label| StatDescriptor {
+                label: unescape(label),
+              })
+              .collect(),
+          })
+        }
+        Some("distribution") => {
+          let title = unescape(field(&mut fields)?);
+          let (unit, display_unit) =
+            (read_unit(&mut fields)?, read_unit(&mut fields)?);
+          let view = match field(&mut fields)? {
+            "histogram" => DistributionView::Histogram,
+            "heatmap" => DistributionView::Heatmap,
+            "fan" => DistributionView::PercentileFan,
+            view => return Err(invalid(&format!("unknown view `{}`", view))),
+          };
+          recorded.distribution_groups.push(DistributionDescriptor {
+            title,
+            unit,
+            display_unit,
+            view,
+          })
+        }
+        Some("phase") => {
+          let (title, x, y) = (
+            unescape(field(&mut fields)?),
+            unescape(field(&mut fields)?),
+            unescape(field(&mut fields)?),
+          );
+          let coloring = match field(&mut fields)? {
+            "gradient" => PhaseColoring::Gradient,
+            "trail" => PhaseColoring::Trail,
+            coloring => {
+              return Err(invalid(&format!("unknown coloring `{}`", coloring)))
+            }
+          };
+          recorded.phase_groups.push(PhaseDescriptor {
+            title,
+            x,
+            y,
+            coloring,
+            trail: parse(field(&mut fields)?)?,
+          })
+        }
+        Some("annotation") => recorded.annotations.push(Annotation::new(
+          parse(field(&mut fields)?)?,
+          unescape(field(&mut fields)?),
+        )),
+        Some("sample") => {
+          let tick = parse(field(&mut fields)?)?;
+          let mut values = Vec::with_capacity(recorded.groups.len());
+          for group in recorded.groups.iter() {
+            values.push(
+              (0..group.stats.len())
+                .map(|_| parse(field(&mut fields)?))
+                .collect::<io::Result<_>>()?,
+            );
+          }
+          let points = (0..recorded.phase_groups.len())
+            .map(|_| {
+              Ok((parse(field(&mut fields)?)?, parse(field(&mut fields)?)?))
+            })
+            .collect::<io::Result<_>>()?;
+          recorded.samples.push((tick, values));
+          recorded.phase_points.push(points);
+          recorded.histograms.push(vec![]);
+        }
+        Some("histogram") => {
+          let histogram = Histogram {
+            min: parse(field(&mut fields)?)?,
+            max: parse(field(&mut fields)?)?,
+            counts: fields.map(parse).collect::<io::Result<_>>()?,
+          };
+          recorded
+            .histograms
+            .last_mut()
+            .ok_or_else(|| invalid("histogram before any sample"))?
+            .push(histogram);
+        }
+        Some("") | None => {}
+        Some(kind) => {
+          return Err(invalid(&format!("unknown record `{}`", kind)))
+        }
+      }
+    }
+    let complete = all.iter().all(|recorded| {
+      recorded.histograms.iter().all(|histograms| {
+        histograms.len() == recorded.distribution_groups.len()
+      })
+    });
+    if !complete {
+      return Err(invalid("sample missing histograms"));
+    }
+    Ok(all)
+  }
+
+  /// Reads the CSV written by `write_source_csv`. Units are read from the
+  /// headers as plain symbols, so values stay in the units they were shown
+  /// in, and the tick mapping is taken from the first samples. A
+  /// `timestamp` column dates time 0 for a calendar time axis.
+  pub fn read_csv<R: BufRead>(reader: R) -> io::Result<Self> {
+    let mut lines = reader.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    let columns = split_csv(&header);
+    let time = columns.get(1).map(|column| split_unit(column));
+    let time_unit = match (columns.first(), time) {
+      (Some(tick), Some(("time", unit))) if tick == "tick" => time_unit(unit),
+      _ => return Err(invalid("expected `tick,time` columns")),
+    };
+    let timestamps = columns.get(2).is_some_and(|column| column == "timestamp");
+    let first_series = if timestamps { 3 } else { 2 };
+
+    let mut groups: Vec<GroupDescriptor> = vec![];
+    for column in columns[first_series..].iter() {
+      let (series, unit) = split_unit(column);
+      let (title, label) = series.split_once('/').unwrap_or(("", series));
+      if groups.last().map(|group| group.title.as_str()) != Some(title) {
+        groups.push(GroupDescriptor {
+          title: title.into(),
+          unit: unit.clone(),
+          display_unit: unit,
+          stats: vec![],
+        });
+      }
+      groups.last_mut().unwrap().stats.push(StatDescriptor {
+        label: label.into(),
+      });
+    }
+
+    let mut samples = vec![];
+    let mut times = vec![];
+    let mut time_axis = TimeAxis::Numeric;
+    for line in lines {
+      let line = line?;
+      if line.is_empty() {
+        continue;
+      }
+      let fields = split_csv(&line);
+      if fields.len() != columns.len() {
+        return Err(invalid("row length differs from the header"));
+      }
+      let tick: usize = parse(&fields[0])?;
+      let time = parse::<f64>(&fields[1])?;
+      times.push((tick, time));
+      // The first timestamp, less the time to it, dates time 0
+      if timestamps && times.len() == 1 {
+        let seconds = time_unit.convert(time, &Unit::seconds());
+        if let (Some(timestamp), Some(seconds)) =
+          (DateTime::parse_iso(&fields[2]), seconds)
+        {
+          time_axis = TimeAxis::Calendar(timestamp.add_seconds(-seconds));
+        }
+      }
+      let mut values = fields[first_series..].iter().map(|value| parse(value));
+      let mut sample = Vec::with_capacity(groups.len());
+      for group in groups.iter() {
+        sample.push(
+          values
+            .by_ref()
+            .take(group.stats.len())
+            .collect::<io::Result<_>>()?,
+        );
+      }
+      samples.push((tick, sample));
+    }
+
+    let time_per_tick = match times[..] {
+      [(tick_a, time_a), (tick_b, time_b), ..] if tick_b > tick_a => {
+        (time_b - time_a) / (tick_b - tick_a) as f64
+      }
+      _ => 1.0,
+    };
+    let time_offset = times
+      .first()
+      .map_or(0.0, |(tick, time)| time - *tick as f64 * time_per_tick);
+    Ok(Self {
+      time_unit: time_unit.clone(),
+      display_time_unit: time_unit,
+      time_axis,
+      time_offset,
+      time_per_tick,
+      groups,
+      samples,
+      distribution_groups: vec![],
+      histograms: vec![],
+      phase_groups: vec![],
+      phase_points: vec![],
+      annotations: vec![],
+    })
+  }
+}
+
+/// A unit's symbol, base, factor and prefixes, as tab separated fields.
+fn unit_fields(unit: &Unit) -> String {
+  let prefixes = match unit.prefixes {
+    Prefixes::None => "none",
+    Prefixes::Si => "si",
+    Prefixes::Count => "count",
+  };
+  format!(
+    "{}\t{}\t{}\t{}",
+    escape(&unit.symbol),
+    escape(&unit.base),
+    unit.factor,
+    prefixes
+  )
+}
+
+fn read_unit<'a>(
+  fields: &mut impl Iterator<Item = &'a str>,
+) -> io::Result<Unit> {
+  Ok(Unit {
+    symbol: unescape(field(fields)?),
+    base: unescape(field(fields)?),
+    factor: parse(field(fields)?)?,
+    prefixes: match field(fields)? {
+      "none" => Prefixes::None,
+      "si" => Prefixes::Si,
+      "count" => Prefixes::Count,
+      prefixes => {
+        return Err(invalid(&format!("unknown prefixes `{}`", prefixes)))
+      }
+    },
+  })
+}
+
+/// Splits a CSV header into its name and the unit in brackets after it.
+fn split_unit(header: &str) -> (&str, Unit) {
+  match header
+    .strip_suffix(')')
+    .and_then(|rest| rest.rsplit_once(" ("))
+  {
+    Some((name, symbol)) => (name, Unit::new(symbol)),
+    None => (header, Unit::new("")),
+  }
+}
+
+/// The typed unit of a time symbol, so that times in seconds, minutes,
+/// hours or days convert to dates.
+fn time_unit(unit: Unit) -> Unit {
+  match unit.symbol.as_str() {
+    "s" => Unit::seconds(),
+    "min" => Unit::minutes(),
+    "h" => Unit::hours(),
+    "d" => Unit::days(),
+    _ => unit,
+  }
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn field<'a>(
+  fields: &mut impl Iterator<Item = &'a str>,
+) -> io::Result<&'a str> {
+  fields.next().ok_or_else(|| invalid("missing field"))
+}
+
+fn parse<T: std::str::FromStr>(field: &str) -> io::Result<T> {
+  field
+    .parse()
+    .map_err(|_| invalid(&format!("can't parse `{}`", field)))
+}
+
+/// Escapes the characters that separate fields and records.
+fn escape(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace('\t', "\\t")
+    .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+  let mut unescaped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('t') => unescaped.push('\t'),
+      Some('n') => unescaped.push('\n'),
+      Some(c) => unescaped.push(c),
+      None => {}
+    }
+  }
+  unescaped
+}
+
+/// Splits a CSV line into fields, unquoting quoted ones.
+fn split_csv(line: &str) -> Vec<String> {
+  let mut fields = vec![];
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = line.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      '"' => quoted = !quoted,
+      ',' if !quoted => fields.push(std::mem::take(&mut field)),
+      c => field.push(c),
+    }
+  }
+  fields.push(field);
+  fields
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn distributions_and_phases_round_trip() {
+    let recorded = RecordedStats {
+      time_unit: Unit::seconds(),
+      display_time_unit: Unit::minutes(),
+      time_axis: TimeAxis::Duration,
+      time_offset: 0.0,
+      time_per_tick: 0.5,
+      groups: vec![GroupDescriptor {
+        title: "Speed".into(),
+        unit: Unit::new("m/s"),
+        display_unit: Unit::new("m/s"),
+        stats: vec![StatDescriptor {
+          label: "mean".into(),
+        }],
+      }],
+      samples: vec![(0, vec![vec![1.0]]), (2, vec![vec![1.5]])],
+      distribution_groups: vec![DistributionDescriptor {
+        title: "Ages".into(),
+        unit: Unit::seconds(),
+        display_unit: Unit::hours(),
+        view: DistributionView::PercentileFan,
+      }],
+      histograms: vec![
+        vec![Histogram::from_values(0.0, 4.0, 4, vec![0.5, 1.5, 1.5])],
+        vec![Histogram::from_values(0.0, 4.0, 4, vec![2.5, 3.5])],
+      ],
+      phase_groups: vec![PhaseDescriptor {
+        title: "Prey\tand predators".into(),
+        x: "prey".into(),
+        y: "predators".into(),
+        coloring: PhaseColoring::Trail,
+        trail: 50,
+      }],
+      phase_points: vec![vec![(10.0, 2.0)], vec![(8.0, 3.0)]],
+      annotations: vec![Annotation::new(2, "peak")],
+    };
+
+    let mut written = vec![];
+    write_recording(&recorded, &mut written).unwrap();
+    assert_eq!(RecordedStats::read(&written[..]).unwrap(), recorded);
+
+    // A sample cut off before its histograms is rejected
+    let text = String::from_utf8(written).unwrap();
+    let cut = &text[..text.rfind("histogram").unwrap()];
+    assert!(RecordedStats::read(cut.as_bytes()).is_err());
+  }
+}
@@ -0,0 +1,161 @@
+use super::{
+  workers::Derived, SimStats, Statistics, StatisticsTrackingSimulatorConfig,
+};
+use std::time::{Duration, Instant};
+
+/// When the tracking simulator records a sample.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordSchedule {
+  /// Every `n` ticks.
+  Ticks(usize),
+  /// Every `interval` of mapped time, as given by `Statistics::map_tick_unit`.
+  SimTime(f64),
+  /// At most once per `interval` of wall-clock time.
+  WallClock(Duration),
+  /// Every `min_step` to `max_step` ticks. When a series changes by more than
+  /// `tolerance` relative to the previous sample the step shrinks in
+  /// proportion, and when every series changes by less than a quarter of it
+  /// the step doubles.
+  Adaptive {
+    min_step: usize,
+    max_step: usize,
+    tolerance: f64,
+  },
+}
+
+impl Default for RecordSchedule {
+  fn default() -> Self {
+    RecordSchedule::Ticks(1)
+  }
+}
+
+/// Tracks when the last sample was recorded against a schedule.
+pub(crate) struct Scheduler {
+  schedule: RecordSchedule,
+  last_tick: usize,
+  last_time: f64,
+  last_instant: Instant,
+  step: usize,
+}
+
+impl Scheduler {
+  pub fn new(schedule: RecordSchedule, time: f64) -> Self {
+    let step = match schedule {
+      RecordSchedule::Ticks(step) => step.max(1),
+      RecordSchedule::Adaptive { min_step, .. } => min_step.max(1),
+      _ => 1,
+    };
+    Self {
+      schedule,
+      last_tick: 0,
+      last_time: time,
+      last_instant: Instant::now(),
+      step,
+    }
+  }
+
+  /// Whether to record at `tick`, which maps to `time`.
+  pub fn is_due(&self, tick: usize, time: f64) -> bool {
+    match self.schedule {
+      RecordSchedule::Ticks(_) => tick % self.step == 0,
+      RecordSchedule::SimTime(interval) => time - self.last_time >= interval,
+      RecordSchedule::WallClock(interval) => {
+        self.last_instant.elapsed() >= interval
+      }
+      RecordSchedule::Adaptive { .. } => tick - self.last_tick >= self.step,
+    }
+  }
+
+  /// Notes that a sample is being recorded.
+  pub fn recorded(&mut self, tick: usize, time: f64) {
+    self.last_tick = tick;
+    self.last_time = time;
+    self.last_instant = Instant::now();
+  }
+
+  /// Adjusts the adaptive step. `change` is the largest relative change of
+  /// any series between the two latest samples.
+  pub fn adapt(&mut self, change: f64) {
+    if let RecordSchedule::Adaptive {
+      min_step,
+      max_step,
+      tolerance,
+    } = self.schedule
+    {
+      if change > tolerance {
+        let step = self.step as f64 * tolerance / change;
+        self.step = (step as usize).max(min_step.max(1));
+      } else if change < tolerance / 4.0 {
+        self.step = (self.step * 2).min(max_step.max(1));
+      }
+    }
+  }
+
+  /// Whether the adaptive step needs the change between samples.
+  pub fn is_adaptive(&self) -> bool {
+    matches!(self.schedule, RecordSchedule::Adaptive { .. })
+  }
+}
+
+/// Decides which ticks one statistics type samples, and hands the samples to
+/// its `SimStats`.
+pub(crate) struct Sampler {
+  scheduler: Scheduler,
+  /// How many samples have been scheduled for recording, including any not
+  /// yet derived.
+  records: usize,
+  summarize_every_tick: bool,
+}
+
+impl Sampler {
+  pub fn new<TState, TStatistics: Statistics<TState>>(
+    config: &StatisticsTrackingSimulatorConfig,
+  ) -> Self {
+    Self {
+      scheduler: Scheduler::new(
+        config.schedule.clone(),
+        TStatistics::map_tick_unit(0),
+      ),
+      // The initial sample is recorded by `SimStats::new`
+      records: 1,
+      summarize_every_tick: config.summarize_every_tick(),
+    }
+  }
+
+  /// Whether to sample `tick` and, if so, whether to record the sample and
+  /// which groups it needs.
+  pub fn sample<TState, TStatistics: Statistics<TState>>(
+    &mut self,
+    tick: usize,
+  ) -> Option<(bool, Vec<bool>)> {
+    let time = TStatistics::map_tick_unit(tick);
+    let record = self.scheduler.is_due(tick, time);
+    if !record && !self.summarize_every_tick {
+      return None;
+    }
+    let due = SimStats::<TState, TStatistics>::groups_due(
+      Some(self.records).filter(|_| record),
+    );
+    if record {
+      self.records += 1;
+      self.scheduler.recorded(tick, time);
+    }
+    Some((record, due))
+  }
+
+  pub fn apply<TState, TStatistics: Statistics<TState>>(
+    &mut self,
+    stats: &mut SimStats<TState, TStatistics>,
+    derived: Derived<TStatistics>,
+  ) {
+    if derived.record {
+      if self.scheduler.is_adaptive() {
+        let change = stats.relative_change(&derived.stats);
+        self.scheduler.adapt(change);
+      }
+      stats.record(derived.tick, derived.stats);
+    } else {
+      stats.summarize(&derived.stats);
+    }
+  }
+}
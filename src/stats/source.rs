@@ -0,0 +1,568 @@
+use super::{
+  format_duration, Annotation, DateTime, DistributionGroup, DistributionView,
+  Histogram, PhaseColoring, PhaseGroup, SimStats, Statistics, StatisticsGroup,
+  TimeAxis, Unit,
+};
+use std::io::Write;
+
+/// One recorded series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatDescriptor {
+  pub label: String,
+}
+
+/// A chart's worth of series, as described by a `StatisticsGroup`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupDescriptor {
+  pub title: String,
+  pub unit: Unit,
+  /// The unit values are shown in, which `unit` converts to.
+  pub display_unit: Unit,
+  pub stats: Vec<StatDescriptor>,
+}
+
+impl GroupDescriptor {
+  pub fn of<TState, TStatistics: Statistics<TState>>(
+    group: &StatisticsGroup<TState, TStatistics>,
+  ) -> Self {
+    Self {
+      title: group.title.clone(),
+      unit: group.unit.clone(),
+      display_unit: group.shown_unit().clone(),
+      stats: group
+        .names
+        .iter()
+        .map(|name| StatDescriptor {
+          label: name.to_string(),
+        })
+        .collect(),
+    }
+  }
+
+  /// A recorded value in the display unit.
+  pub fn shown_value(&self, value: f64) -> f64 {
+    self
+      .unit
+      .convert(value, &self.display_unit)
+      .unwrap_or(value)
+  }
+}
+
+/// A distribution-valued statistic, as described by a `DistributionGroup`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistributionDescriptor {
+  pub title: String,
+  pub unit: Unit,
+  /// The unit values are shown in, which `unit` converts to.
+  pub display_unit: Unit,
+  pub view: DistributionView,
+}
+
+impl DistributionDescriptor {
+  pub fn of<TState, TStatistics: Statistics<TState>>(
+    group: &DistributionGroup<TState, TStatistics>,
+  ) -> Self {
+    Self {
+      title: group.title.clone(),
+      unit: group.unit.clone(),
+      display_unit: group.shown_unit().clone(),
+      view: group.view,
+    }
+  }
+
+  /// A recorded value in the display unit.
+  pub fn shown_value(&self, value: f64) -> f64 {
+    self
+      .unit
+      .convert(value, &self.display_unit)
+      .unwrap_or(value)
+  }
+}
+
+/// One statistic plotted against another, as described by a `PhaseGroup`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseDescriptor {
+  pub title: String,
+  /// The labels of the x and y statistics.
+  pub x: String,
+  pub y: String,
+  pub coloring: PhaseColoring,
+  pub trail: usize,
+}
+
+impl PhaseDescriptor {
+  pub fn of<TState, TStatistics: Statistics<TState>>(
+    group: &PhaseGroup<TState, TStatistics>,
+  ) -> Self {
+    Self {
+      title: group.title.clone(),
+      x: group.x.to_string(),
+      y: group.y.to_string(),
+      coloring: group.coloring,
+      trail: group.trail,
+    }
+  }
+}
+
+/// Recorded statistics behind an object-safe interface, so sources of
+/// different types, or with no type at all, can be charted side by side.
+/// Series are addressed by group and stat index, following `groups`, and
+/// distribution and phase groups by their index.
+pub trait StatisticsSource {
+  /// The unit `map_tick` maps ticks to.
+  fn time_unit(&self) -> Unit;
+
+  /// The unit to show time in, which `time_unit` converts to.
+  fn display_time_unit(&self) -> Unit {
+    self.time_unit()
+  }
+
+  /// The time a tick maps to, as `Statistics::map_tick_unit`.
+  fn map_tick(&self, tick: usize) -> f64;
+
+  /// The time of a tick in the display unit.
+  fn shown_time(&self, tick: usize) -> f64 {
+    let time = self.map_tick(tick);
+    self
+      .time_unit()
+      .convert(time, &self.display_time_unit())
+      .unwrap_or(time)
+  }
+
+  fn time_axis(&self) -> TimeAxis {
+    TimeAxis::Numeric
+  }
+
+  /// The seconds from time 0 to a tick, if the time unit converts to
+  /// seconds.
+  fn elapsed_seconds(&self, tick: usize) -> Option<f64> {
+    self
+      .time_unit()
+      .convert(self.map_tick(tick), &Unit::seconds())
+  }
+
+  /// The date and time of a tick, on calendar time axes.
+  fn timestamp(&self, tick: usize) -> Option<DateTime> {
+    match self.time_axis() {
+      TimeAxis::Calendar(start) => {
+        Some(start.add_seconds(self.elapsed_seconds(tick)?))
+      }
+      _ => None,
+    }
+  }
+
+  /// A tick's date and time on calendar time axes, or its elapsed time on
+  /// duration ones, as readouts show it.
+  fn time_readout(&self, tick: usize) -> Option<String> {
+    let seconds = self.elapsed_seconds(tick)?;
+    match self.time_axis() {
+      TimeAxis::Numeric => None,
+      TimeAxis::Duration => Some(format_duration(seconds)),
+      TimeAxis::Calendar(start) => Some(start.add_seconds(seconds).to_string()),
+    }
+  }
+
+  fn groups(&self) -> &[GroupDescriptor];
+
+  /// How many samples have been recorded.
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// The tick of a sample. Samples are in tick order.
+  fn tick(&self, sample: usize) -> usize;
+
+  fn value(&self, sample: usize, group: usize, stat: usize) -> f64;
+
+  /// The all-time minimum and maximum of a series, NaN when it has no finite
+  /// values.
+  fn extremes(&self, group: usize, stat: usize) -> (f64, f64);
+
+  /// The finite values of a series, in sample order, for analyses such as
+  /// those in `timeseries`.
+  fn series(&self, group: usize, stat: usize) -> Vec<f64> {
+    (0..self.len())
+      .map(|sample| self.value(sample, group, stat))
+      .filter(|value| value.is_finite())
+      .collect()
+  }
+
+  /// Series computed from the history of a line group, such as moving
+  /// averages, as labelled `(tick, value)` points.
+  fn derived_series(&self, _group: usize) -> Vec<(String, Vec<(usize, f64)>)> {
+    vec![]
+  }
+
+  /// The value of each of a group's derived series at a sample, labelled.
+  fn derived_values(
+    &self,
+    _sample: usize,
+    _group: usize,
+  ) -> Vec<(String, f64)> {
+    vec![]
+  }
+
+  fn distribution_groups(&self) -> &[DistributionDescriptor] {
+    &[]
+  }
+
+  /// The histogram of a distribution group at a sample, or a single empty
+  /// bin when the sample has none.
+  fn histogram(&self, _sample: usize, _group: usize) -> Histogram {
+    Histogram::new(0.0, 1.0, 1)
+  }
+
+  fn phase_groups(&self) -> &[PhaseDescriptor] {
+    &[]
+  }
+
+  /// The x and y values of a phase group at a sample.
+  fn phase_point(&self, _sample: usize, _group: usize) -> (f64, f64) {
+    (f64::NAN, f64::NAN)
+  }
+
+  fn annotations(&self) -> &[Annotation] {
+    &[]
+  }
+}
+
+impl<TState, TStatistics: Statistics<TState>> StatisticsSource
+  for SimStats<TState, TStatistics>
+{
+  fn time_unit(&self) -> Unit {
+    TStatistics::get_time_unit()
+  }
+
+  fn display_time_unit(&self) -> Unit {
+    shown_time_unit::<TState, TStatistics>()
+  }
+
+  fn shown_time(&self, tick: usize) -> f64 {
+    shown_time::<TState, TStatistics>(tick)
+  }
+
+  fn time_axis(&self) -> TimeAxis {
+    TStatistics::get_time_axis()
+  }
+
+  fn elapsed_seconds(&self, tick: usize) -> Option<f64> {
+    elapsed_seconds::<TState, TStatistics>(tick)
+  }
+
+  fn map_tick(&self, tick: usize) -> f64 {
+    TStatistics::map_tick_unit(tick)
+  }
+
+  fn groups(&self) -> &[GroupDescriptor] {
+    &self.descriptors
+  }
+
+  fn len(&self) -> usize {
+    self.statistics.len()
+  }
+
+  fn tick(&self, sample: usize) -> usize {
+    self.statistics[sample].0
+  }
+
+  fn value(&self, sample: usize, group: usize, stat: usize) -> f64 {
+    self.statistics[sample]
+      .1
+      .get_value(self.ids[group][stat].clone())
+  }
+
+  fn extremes(&self, group: usize, stat: usize) -> (f64, f64) {
+    (
+      self.series_min_values[group][stat],
+      self.series_max_values[group][stat],
+    )
+  }
+
+  fn derived_series(&self, group: usize) -> Vec<(String, Vec<(usize, f64)>)> {
+    let group = &TStatistics::get_groups()[group];
+    group
+      .derived
+      .iter()
+      .map(|derived| derived.label.clone())
+      .zip(group.derived_values(&self.statistics))
+      .collect()
+  }
+
+  fn derived_values(&self, sample: usize, group: usize) -> Vec<(String, f64)> {
+    let group = &TStatistics::get_groups()[group];
+    group
+      .derived
+      .iter()
+      .map(|derived| derived.label.clone())
+      .zip(group.derived_values_at(&self.statistics, sample))
+      .collect()
+  }
+
+  fn distribution_groups(&self) -> &[DistributionDescriptor] {
+    &self.distribution_descriptors
+  }
+
+  fn histogram(&self, sample: usize, group: usize) -> Histogram {
+    self.statistics[sample]
+      .1
+      .get_distribution(self.distribution_ids[group].clone())
+      .unwrap_or_else(|| Histogram::new(0.0, 1.0, 1))
+  }
+
+  fn phase_groups(&self) -> &[PhaseDescriptor] {
+    &self.phase_descriptors
+  }
+
+  fn phase_point(&self, sample: usize, group: usize) -> (f64, f64) {
+    let (x, y) = &self.phase_ids[group];
+    let stats = &self.statistics[sample].1;
+    (stats.get_value(x.clone()), stats.get_value(y.clone()))
+  }
+
+  fn annotations(&self) -> &[Annotation] {
+    &self.annotations
+  }
+}
+
+/// The display time unit of a statistics type, or its time unit when the
+/// display unit doesn't convert from it.
+pub(crate) fn shown_time_unit<TState, TStatistics: Statistics<TState>>() -> Unit
+{
+  let display = TStatistics::get_display_time_unit();
+  if TStatistics::get_time_unit().converts_to(&display) {
+    display
+  } else {
+    TStatistics::get_time_unit()
+  }
+}
+
+/// The time of a tick in the display time unit of a statistics type.
+pub(crate) fn shown_time<TState, TStatistics: Statistics<TState>>(
+  tick: usize,
+) -> f64 {
+  let time = TStatistics::map_tick_unit(tick);
+  TStatistics::get_time_unit()
+    .convert(time, &shown_time_unit::<TState, TStatistics>())
+    .unwrap_or(time)
+}
+
+/// The seconds from time 0 to a tick of a statistics type, if its time unit
+/// converts to seconds.
+pub(crate) fn elapsed_seconds<TState, TStatistics: Statistics<TState>>(
+  tick: usize,
+) -> Option<f64> {
+  TStatistics::get_time_unit()
+    .convert(TStatistics::map_tick_unit(tick), &Unit::seconds())
+}
+
+/// The date and time of a tick of a statistics type on a calendar time axis.
+pub(crate) fn timestamp<TState, TStatistics: Statistics<TState>>(
+  tick: usize,
+) -> Option<DateTime> {
+  match TStatistics::get_time_axis() {
+    TimeAxis::Calendar(start) => {
+      Some(start.add_seconds(elapsed_seconds::<TState, TStatistics>(tick)?))
+    }
+    _ => None,
+  }
+}
+
+/// A copy of the samples of any source, holding no statistics type. Ticks
+/// are mapped to time linearly, by the source's mapping of ticks 0 and 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedStats {
+  pub time_unit: Unit,
+  pub display_time_unit: Unit,
+  pub time_axis: TimeAxis,
+  /// The time of tick 0, and the time per tick.
+  pub time_offset: f64,
+  pub time_per_tick: f64,
+  pub groups: Vec<GroupDescriptor>,
+  /// Each sample's tick, and its values indexed by group then stat.
+  pub samples: Vec<(usize, Vec<Vec<f64>>)>,
+  pub distribution_groups: Vec<DistributionDescriptor>,
+  /// Each sample's histograms, indexed by distribution group.
+  pub histograms: Vec<Vec<Histogram>>,
+  pub phase_groups: Vec<PhaseDescriptor>,
+  /// Each sample's x and y values, indexed by phase group.
+  pub phase_points: Vec<Vec<(f64, f64)>>,
+  pub annotations: Vec<Annotation>,
+}
+
+impl RecordedStats {
+  pub fn from_source(source: &dyn StatisticsSource) -> Self {
+    let groups = source.groups().to_vec();
+    let samples = (0..source.len())
+      .map(|sample| {
+        let values = groups
+          .iter()
+          .enumerate()
+          .map(|(g, group)| {
+            (0..group.stats.len())
+              .map(|s| source.value(sample, g, s))
+              .collect()
+          })
+          .collect();
+        (source.tick(sample), values)
+      })
+      .collect();
+    let distribution_groups = source.distribution_groups().to_vec();
+    let histograms = (0..source.len())
+      .map(|sample| {
+        (0..distribution_groups.len())
+          .map(|g| source.histogram(sample, g))
+          .collect()
+      })
+      .collect();
+    let phase_groups = source.phase_groups().to_vec();
+    let phase_points = (0..source.len())
+      .map(|sample| {
+        (0..phase_groups.len())
+          .map(|g| source.phase_point(sample, g))
+          .collect()
+      })
+      .collect();
+    let time_offset = source.map_tick(0);
+    Self {
+      time_unit: source.time_unit(),
+      display_time_unit: source.display_time_unit(),
+      time_axis: source.time_axis(),
+      time_offset,
+      time_per_tick: source.map_tick(1) - time_offset,
+      groups,
+      samples,
+      distribution_groups,
+      histograms,
+      phase_groups,
+      phase_points,
+      annotations: source.annotations().to_vec(),
+    }
+  }
+}
+
+impl StatisticsSource for RecordedStats {
+  fn time_unit(&self) -> Unit {
+    self.time_unit.clone()
+  }
+
+  fn display_time_unit(&self) -> Unit {
+    self.display_time_unit.clone()
+  }
+
+  fn time_axis(&self) -> TimeAxis {
+    self.time_axis
+  }
+
+  fn map_tick(&self, tick: usize) -> f64 {
+    self.time_offset + tick as f64 * self.time_per_tick
+  }
+
+  fn groups(&self) -> &[GroupDescriptor] {
+    &self.groups
+  }
+
+  fn len(&self) -> usize {
+    self.samples.len()
+  }
+
+  fn tick(&self, sample: usize) -> usize {
+    self.samples[sample].0
+  }
+
+  fn value(&self, sample: usize, group: usize, stat: usize) -> f64 {
+    self.samples[sample].1[group][stat]
+  }
+
+  fn extremes(&self, group: usize, stat: usize) -> (f64, f64) {
+    self
+      .samples
+      .iter()
+      .map(|(_, values)| values[group][stat])
+      .filter(|value| value.is_finite())
+      .fold((f64::NAN, f64::NAN), |(min, max), value| {
+        (min.min(value), max.max(value))
+      })
+  }
+
+  fn distribution_groups(&self) -> &[DistributionDescriptor] {
+    &self.distribution_groups
+  }
+
+  fn histogram(&self, sample: usize, group: usize) -> Histogram {
+    self.histograms[sample][group].clone()
+  }
+
+  fn phase_groups(&self) -> &[PhaseDescriptor] {
+    &self.phase_groups
+  }
+
+  fn phase_point(&self, sample: usize, group: usize) -> (f64, f64) {
+    self.phase_points[sample][group]
+  }
+
+  fn annotations(&self) -> &[Annotation] {
+    &self.annotations
+  }
+}
+
+/// Writes every sample as a `tick,time` row followed by each series, with
+/// series headed `group/stat`. Times and values are in their display units,
+/// which follow headers in brackets. Sources on calendar time axes add an
+/// ISO 8601 `timestamp` column after `time`.
+pub fn write_source_csv<W: Write>(
+  source: &dyn StatisticsSource,
+  mut writer: W,
+) -> std::io::Result<()> {
+  write!(
+    writer,
+    "tick,\"{}\"",
+    quoted(&with_unit("time", &source.display_time_unit()))
+  )?;
+  let calendar = matches!(source.time_axis(), TimeAxis::Calendar(_));
+  if calendar {
+    write!(writer, ",timestamp")?;
+  }
+  for group in source.groups() {
+    for stat in group.stats.iter() {
+      let header = format!("{}/{}", group.title, stat.label);
+      write!(
+        writer,
+        ",\"{}\"",
+        quoted(&with_unit(&header, &group.display_unit))
+      )?;
+    }
+  }
+  writeln!(writer)?;
+  for sample in 0..source.len() {
+    let tick = source.tick(sample);
+    write!(writer, "{},{}", tick, source.shown_time(tick))?;
+    if calendar {
+      match source.timestamp(tick) {
+        Some(timestamp) => write!(writer, ",{}", timestamp)?,
+        None => write!(writer, ",")?,
+      }
+    }
+    for (g, group) in source.groups().iter().enumerate() {
+      for s in 0..group.stats.len() {
+        write!(writer, ",{}", group.shown_value(source.value(sample, g, s)))?;
+      }
+    }
+    writeln!(writer)?;
+  }
+  Ok(())
+}
+
+/// A column header followed by its unit, if it has one.
+fn with_unit(header: &str, unit: &Unit) -> String {
+  if unit.symbol.is_empty() {
+    header.into()
+  } else {
+    format!("{} ({})", header, unit)
+  }
+}
+
+fn quoted(field: &str) -> String {
+  field.replace('"', "\"\"")
+}
@@ -0,0 +1,542 @@
+use std::f64::consts::PI;
+
+/// Which summaries to keep for every series, and how often to update them.
+#[derive(Clone, Debug)]
+pub struct SummaryConfig {
+  update: SummaryUpdate,
+  ewma_alpha: f64,
+  quantiles: Vec<f64>,
+  compression: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SummaryUpdate {
+  /// Derive statistics every tick, even when they aren't recorded.
+  EveryTick,
+  /// Only update when a sample is recorded.
+  OnRecord,
+}
+
+impl Default for SummaryConfig {
+  fn default() -> Self {
+    Self {
+      update: SummaryUpdate::OnRecord,
+      ewma_alpha: 0.1,
+      quantiles: vec![0.05, 0.5, 0.95],
+      compression: 100.0,
+    }
+  }
+}
+
+impl SummaryConfig {
+  pub fn update(mut self, update: SummaryUpdate) -> Self {
+    self.update = update;
+    self
+  }
+
+  pub fn ewma_alpha(mut self, alpha: f64) -> Self {
+    self.ewma_alpha = alpha;
+    self
+  }
+
+  /// The quantiles tracked with P² estimators. Other quantiles are answered
+  /// from the t-digest.
+  pub fn quantiles(mut self, quantiles: Vec<f64>) -> Self {
+    self.quantiles = quantiles;
+    self
+  }
+
+  /// The t-digest compression. Higher is more accurate and uses more memory.
+  pub fn compression(mut self, compression: f64) -> Self {
+    self.compression = compression;
+    self
+  }
+
+  pub(crate) fn get_update(&self) -> SummaryUpdate {
+    self.update
+  }
+}
+
+/// Online aggregates of one series, updated a sample at a time.
+#[derive(Clone, Debug)]
+pub struct SeriesSummary {
+  moments: Welford,
+  min: f64,
+  max: f64,
+  ewma: Ewma,
+  p2: Vec<P2Quantile>,
+  digest: TDigest,
+}
+
+impl SeriesSummary {
+  pub fn new(config: &SummaryConfig) -> Self {
+    Self {
+      moments: Welford::default(),
+      min: f64::NAN,
+      max: f64::NAN,
+      ewma: Ewma::new(config.ewma_alpha),
+      p2: config
+        .quantiles
+        .iter()
+        .map(|&q| P2Quantile::new(q))
+        .collect(),
+      digest: TDigest::new(config.compression),
+    }
+  }
+
+  /// Adds a sample. Non-finite samples are ignored, as an infinity would
+  /// pin the quantile markers and centroids.
+  pub fn push(&mut self, value: f64) {
+    if !value.is_finite() {
+      return;
+    }
+    self.moments.push(value);
+    self.min = f64::min(self.min, value);
+    self.max = f64::max(self.max, value);
+    self.ewma.push(value);
+    for p2 in self.p2.iter_mut() {
+      p2.push(value);
+    }
+    self.digest.push(value);
+  }
+
+  pub fn count(&self) -> usize {
+    self.moments.count()
+  }
+
+  pub fn mean(&self) -> f64 {
+    self.moments.mean()
+  }
+
+  /// The sample variance.
+  pub fn variance(&self) -> f64 {
+    self.moments.variance()
+  }
+
+  pub fn std_dev(&self) -> f64 {
+    self.moments.variance().sqrt()
+  }
+
+  pub fn min(&self) -> f64 {
+    self.min
+  }
+
+  pub fn max(&self) -> f64 {
+    self.max
+  }
+
+  pub fn ewma(&self) -> f64 {
+    self.ewma.value()
+  }
+
+  /// The P² estimate of `q`, if `q` is one of the configured quantiles.
+  pub fn p2_quantile(&self, q: f64) -> Option<f64> {
+    self
+      .p2
+      .iter()
+      .find(|p2| (p2.p - q).abs() < f64::EPSILON)
+      .map(|p2| p2.value())
+  }
+
+  /// The t-digest estimate of any quantile.
+  pub fn quantile(&self, q: f64) -> f64 {
+    self.digest.quantile(q)
+  }
+}
+
+/// Welford's online mean and variance.
+#[derive(Clone, Debug, Default)]
+pub struct Welford {
+  count: usize,
+  mean: f64,
+  m2: f64,
+}
+
+impl Welford {
+  pub fn push(&mut self, value: f64) {
+    self.count += 1;
+    let delta = value - self.mean;
+    self.mean += delta / self.count as f64;
+    self.m2 += delta * (value - self.mean);
+  }
+
+  pub fn count(&self) -> usize {
+    self.count
+  }
+
+  pub fn mean(&self) -> f64 {
+    if self.count == 0 {
+      f64::NAN
+    } else {
+      self.mean
+    }
+  }
+
+  pub fn variance(&self) -> f64 {
+    if self.count < 2 {
+      f64::NAN
+    } else {
+      self.m2 / (self.count - 1) as f64
+    }
+  }
+}
+
+/// An exponentially weighted moving average.
+#[derive(Clone, Debug)]
+pub struct Ewma {
+  alpha: f64,
+  value: Option<f64>,
+}
+
+impl Ewma {
+  pub fn new(alpha: f64) -> Self {
+    Self { alpha, value: None }
+  }
+
+  pub fn push(&mut self, value: f64) {
+    self.value = Some(match self.value {
+      Some(ewma) => ewma + self.alpha * (value - ewma),
+      None => value,
+    });
+  }
+
+  pub fn value(&self) -> f64 {
+    self.value.unwrap_or(f64::NAN)
+  }
+}
+
+/// Jain and Chlamtac's P² estimator of a single quantile in constant space.
+#[derive(Clone, Debug)]
+pub struct P2Quantile {
+  p: f64,
+  heights: Vec<f64>,
+  positions: [f64; 5],
+  desired: [f64; 5],
+  increments: [f64; 5],
+}
+
+impl P2Quantile {
+  pub fn new(p: f64) -> Self {
+    Self {
+      p,
+      heights: Vec::with_capacity(5),
+      positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+      desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+      increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+    }
+  }
+
+  pub fn push(&mut self, value: f64) {
+    // Until there are five markers the samples are kept verbatim
+    if self.heights.len() < 5 {
+      let i = self.heights.partition_point(|h| *h < value);
+      self.heights.insert(i, value);
+      return;
+    }
+
+    let h = &mut self.heights;
+    let k = if value < h[0] {
+      h[0] = value;
+      0
+    } else if value >= h[4] {
+      h[4] = value;
+      3
+    } else {
+      (1..5).find(|&i| value < h[i]).unwrap() - 1
+    };
+
+    for position in self.positions[k + 1..].iter_mut() {
+      *position += 1.0;
+    }
+    for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+      *desired += increment;
+    }
+
+    for i in 1..4 {
+      let d = self.desired[i] - self.positions[i];
+      let n = &self.positions;
+      if (d >= 1.0 && n[i + 1] - n[i] > 1.0)
+        || (d <= -1.0 && n[i - 1] - n[i] < -1.0)
+      {
+        let d = d.signum();
+        let parabolic = self.parabolic(i, d);
+        self.heights[i] = if self.heights[i - 1] < parabolic
+          && parabolic < self.heights[i + 1]
+        {
+          parabolic
+        } else {
+          self.linear(i, d)
+        };
+        self.positions[i] += d;
+      }
+    }
+  }
+
+  fn parabolic(&self, i: usize, d: f64) -> f64 {
+    let (q, n) = (&self.heights, &self.positions);
+    q[i]
+      + d / (n[i + 1] - n[i - 1])
+        * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+          + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+  }
+
+  fn linear(&self, i: usize, d: f64) -> f64 {
+    let (q, n) = (&self.heights, &self.positions);
+    let j = if d > 0.0 { i + 1 } else { i - 1 };
+    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+  }
+
+  pub fn value(&self) -> f64 {
+    if self.heights.len() < 5 {
+      // Too few samples for markers, so answer exactly
+      if self.heights.is_empty() {
+        return f64::NAN;
+      }
+      let i = (self.p * (self.heights.len() - 1) as f64).round() as usize;
+      return self.heights[i];
+    }
+    self.heights[2]
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Centroid {
+  mean: f64,
+  weight: f64,
+}
+
+/// A merging t-digest, answering any quantile with accuracy concentrated in
+/// the tails.
+#[derive(Clone, Debug)]
+pub struct TDigest {
+  compression: f64,
+  centroids: Vec<Centroid>,
+  buffer: Vec<f64>,
+  min: f64,
+  max: f64,
+}
+
+impl TDigest {
+  pub fn new(compression: f64) -> Self {
+    Self {
+      compression,
+      centroids: vec![],
+      buffer: vec![],
+      min: f64::NAN,
+      max: f64::NAN,
+    }
+  }
+
+  pub fn push(&mut self, value: f64) {
+    self.min = f64::min(self.min, value);
+    self.max = f64::max(self.max, value);
+    self.buffer.push(value);
+    if self.buffer.len() >= self.compression as usize * 5 {
+      self.centroids = self.merged();
+      self.buffer.clear();
+    }
+  }
+
+  pub fn count(&self) -> f64 {
+    self.centroids.iter().map(|c| c.weight).sum::<f64>()
+      + self.buffer.len() as f64
+  }
+
+  fn merged(&self) -> Vec<Centroid> {
+    let mut all: Vec<_> = self
+      .centroids
+      .iter()
+      .copied()
+      .chain(
+        self
+          .buffer
+          .iter()
+          .map(|&mean| Centroid { mean, weight: 1.0 }),
+      )
+      .collect();
+    if all.is_empty() {
+      return all;
+    }
+    all.sort_unstable_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap());
+
+    let total: f64 = all.iter().map(|c| c.weight).sum();
+    let k = |q: f64| {
+      self.compression / (2.0 * PI) * (2.0 * q.clamp(0.0, 1.0) - 1.0).asin()
+    };
+
+    let mut merged = Vec::with_capacity(all.len());
+    let mut current = all[0];
+    let mut weight_before = 0.0;
+    for next in all.into_iter().skip(1) {
+      let q_left = weight_before / total;
+      let q_right = (weight_before + current.weight + next.weight) / total;
+      if k(q_right) - k(q_left) <= 1.0 {
+        let weight = current.weight + next.weight;
+        current.mean += (next.mean - current.mean) * next.weight / weight;
+        current.weight = weight;
+      } else {
+        weight_before += current.weight;
+        merged.push(current);
+        current = next;
+      }
+    }
+    merged.push(current);
+    merged
+  }
+
+  pub fn quantile(&self, q: f64) -> f64 {
+    let centroids = if self.buffer.is_empty() {
+      self.centroids.clone()
+    } else {
+      self.merged()
+    };
+    if centroids.is_empty() {
+      return f64::NAN;
+    }
+
+    let total: f64 = centroids.iter().map(|c| c.weight).sum();
+    let target = q.clamp(0.0, 1.0) * total;
+
+    // Interpolate between centroid centres, treating min and max as the
+    // outer edges
+    let mut previous = (0.0, self.min);
+    let mut cumulative = 0.0;
+    for c in centroids.iter() {
+      let centre = cumulative + c.weight / 2.0;
+      if target < centre {
+        let t = (target - previous.0) / (centre - previous.0).max(f64::EPSILON);
+        return previous.1 + t * (c.mean - previous.1);
+      }
+      previous = (centre, c.mean);
+      cumulative += c.weight;
+    }
+    let t = (target - previous.0) / (total - previous.0).max(f64::EPSILON);
+    previous.1 + t.min(1.0) * (self.max - previous.1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A seeded sample of standard normal values, by xorshift and Box-Muller.
+  fn normal_sample(seed: u64, n: usize) -> Vec<f64> {
+    let mut state = seed;
+    let mut uniform = move || {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    };
+    (0..n)
+      .map(|_| {
+        let (u, v) = (uniform(), uniform());
+        (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+      })
+      .collect()
+  }
+
+  /// The fraction of `sorted` below `value`.
+  fn rank(sorted: &[f64], value: f64) -> f64 {
+    sorted.partition_point(|v| *v < value) as f64 / sorted.len() as f64
+  }
+
+  fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted
+  }
+
+  #[test]
+  fn welford_matches_two_pass() {
+    // Offset so a naive sum of squares would lose most of the variance
+    let values: Vec<f64> =
+      normal_sample(7, 10_000).iter().map(|v| 1e6 + v).collect();
+    let mut welford = Welford::default();
+    values.iter().for_each(|v| welford.push(*v));
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance =
+      values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    assert_eq!(welford.count(), values.len());
+    assert!((welford.mean() - mean).abs() < 1e-9 * mean);
+    assert!((welford.variance() - variance).abs() < 1e-8 * variance);
+
+    let mut one = Welford::default();
+    assert!(one.mean().is_nan());
+    one.push(3.0);
+    assert_eq!(one.mean(), 3.0);
+    assert!(one.variance().is_nan());
+  }
+
+  #[test]
+  fn p2_quantiles_are_close_to_exact() {
+    let values = normal_sample(11, 10_000);
+    let exact = sorted(&values);
+    for p in [0.05, 0.25, 0.5, 0.75, 0.95] {
+      let mut p2 = P2Quantile::new(p);
+      values.iter().for_each(|v| p2.push(*v));
+      let error = (rank(&exact, p2.value()) - p).abs();
+      assert!(error < 0.01, "P² {} is off by {} in rank", p, error);
+    }
+  }
+
+  #[test]
+  fn p2_is_exact_below_five_samples() {
+    let mut p2 = P2Quantile::new(0.5);
+    assert!(p2.value().is_nan());
+    for v in [4.0, 1.0, 3.0] {
+      p2.push(v);
+    }
+    assert_eq!(p2.value(), 3.0);
+  }
+
+  #[test]
+  fn t_digest_quantiles_are_close_to_exact() {
+    let values = normal_sample(13, 20_000);
+    let exact = sorted(&values);
+    let mut digest = TDigest::new(100.0);
+    values.iter().for_each(|v| digest.push(*v));
+    assert_eq!(digest.count(), values.len() as f64);
+    for q in [0.001, 0.01, 0.1, 0.5, 0.9, 0.99, 0.999] {
+      let error = (rank(&exact, digest.quantile(q)) - q).abs();
+      // Accuracy is relative to the distance from the nearer tail
+      let allowed = (0.2 * q * (1.0 - q)).min(0.002);
+      assert!(
+        error < allowed,
+        "t-digest {} is off by {} in rank",
+        q,
+        error
+      );
+    }
+    assert_eq!(digest.quantile(0.0), exact[0]);
+    assert_eq!(digest.quantile(1.0), exact[exact.len() - 1]);
+  }
+
+  #[test]
+  fn summary_skips_non_finite_values() {
+    let values = normal_sample(17, 1_000);
+    let mut clean = SeriesSummary::new(&SummaryConfig::default());
+    let mut dirty = SeriesSummary::new(&SummaryConfig::default());
+    for (i, v) in values.iter().enumerate() {
+      clean.push(*v);
+      dirty.push(*v);
+      if i % 100 == 0 {
+        dirty.push(f64::INFINITY);
+        dirty.push(f64::NEG_INFINITY);
+        dirty.push(f64::NAN);
+      }
+    }
+    assert_eq!(dirty.count(), clean.count());
+    assert_eq!(
+      (dirty.mean(), dirty.variance(), dirty.min(), dirty.max()),
+      (clean.mean(), clean.variance(), clean.min(), clean.max())
+    );
+    assert_eq!(dirty.ewma(), clean.ewma());
+    for q in [0.05, 0.5, 0.95] {
+      assert_eq!(dirty.p2_quantile(q), clean.p2_quantile(q));
+      assert_eq!(dirty.quantile(q), clean.quantile(q));
+    }
+  }
+}
@@ -0,0 +1,359 @@
+use super::convergence::moments;
+use std::cmp::Ordering;
+
+/// The autocorrelation of lags `0..=max_lag`, in samples, normalized so lag 0
+/// is 1. It is NaN for constant series.
+///
+/// These analyses assume evenly spaced samples, such as one stat's values
+/// from `StatisticsSource::series` under a fixed schedule. Adaptive schedules
+/// space samples unevenly and skew the results.
+pub fn autocorrelation(values: &[f64], max_lag: usize) -> Vec<f64> {
+  let n = values.len();
+  if n == 0 {
+    return vec![];
+  }
+  // Padding to twice the length keeps the circular correlation from wrapping
+  let mut buffer = centered(values, (2 * n).next_power_of_two());
+  fft(&mut buffer, false);
+  for value in buffer.iter_mut() {
+    *value = (value.0 * value.0 + value.1 * value.1, 0.0);
+  }
+  fft(&mut buffer, true);
+  let variance = buffer[0].0;
+  buffer
+    .iter()
+    .take(max_lag.min(n - 1) + 1)
+    .map(|value| value.0 / variance)
+    .collect()
+}
+
+/// The integrated autocorrelation time, in samples: how many samples it takes
+/// to get one independent one. Uses Sokal's window, summing lags until the
+/// window is `window` times the estimate, and 5 is usual.
+///
+/// Anti-correlated and oscillating series count as independent, at 1. It is
+/// NaN when the window doesn't close within half the series, as the sum over
+/// every lag is always 0.
+pub fn integrated_autocorrelation_time(values: &[f64], window: f64) -> f64 {
+  let rho = autocorrelation(values, values.len() / 2);
+  let mut tau = 1.0;
+  for (lag, rho) in rho.iter().enumerate().skip(1) {
+    tau += 2.0 * rho;
+    if lag as f64 >= window * tau {
+      return tau.max(1.0);
+    }
+  }
+  f64::NAN
+}
+
+/// How many independent samples the series is worth.
+pub fn effective_sample_size(values: &[f64]) -> f64 {
+  values.len() as f64 / integrated_autocorrelation_time(values, 5.0)
+}
+
+/// A mean with an error bar that allows for autocorrelation, from the
+/// spread of the means of consecutive batches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchMeans {
+  pub mean: f64,
+  pub std_error: f64,
+  pub batches: usize,
+  pub batch_size: usize,
+}
+
+impl BatchMeans {
+  /// Splits the series into `batches` batches, dropping the oldest samples
+  /// that don't fill one. Batches should be longer than the autocorrelation
+  /// time for the error to hold.
+  pub fn new(values: &[f64], batches: usize) -> Self {
+    let batch_size = values.len() / batches.max(1);
+    if batches < 2 || batch_size == 0 {
+      return Self {
+        mean: moments(values).mean(),
+        std_error: f64::NAN,
+        batches: 0,
+        batch_size,
+      };
+    }
+    let used = &values[values.len() - batches * batch_size..];
+    let means: Vec<f64> = used
+      .chunks(batch_size)
+      .map(|batch| batch.iter().sum::<f64>() / batch_size as f64)
+      .collect();
+    let means = moments(&means);
+    Self {
+      mean: means.mean(),
+      std_error: (means.variance() / batches as f64).sqrt(),
+      batches,
+      batch_size,
+    }
+  }
+}
+
+/// The power at each frequency of a series, from its periodogram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+  /// In cycles per unit of the spacing given to `new`, from 0 to Nyquist.
+  pub frequencies: Vec<f64>,
+  pub power: Vec<f64>,
+}
+
+impl Spectrum {
+  /// The spectrum of samples `spacing` apart, in ticks or mapped time, which
+  /// frequencies and periods are then measured in. The mean is removed, and
+  /// the series zero-padded to a power of two.
+  pub fn new(values: &[f64], spacing: f64) -> Self {
+    if values.is_empty() {
+      return Self {
+        frequencies: vec![],
+        power: vec![],
+      };
+    }
+    let size = values.len().next_power_of_two();
+    let mut buffer = centered(values, size);
+    fft(&mut buffer, false);
+    let bins = size / 2 + 1;
+    Self {
+      frequencies: (0..bins)
+        .map(|bin| bin as f64 / (size as f64 * spacing))
+        .collect(),
+      power: buffer
+        .iter()
+        .take(bins)
+        .map(|value| {
+          (value.0 * value.0 + value.1 * value.1) / values.len() as f64
+        })
+        .collect(),
+    }
+  }
+
+  /// The periods of the `count` strongest peaks, with their power, strongest
+  /// first. Peaks are local maxima, so a broad one counts once, and their
+  /// periods are interpolated between frequencies.
+  pub fn dominant_periods(&self, count: usize) -> Vec<(f64, f64)> {
+    let power = &self.power;
+    let mut peaks: Vec<(f64, f64)> = (1..power.len())
+      .filter(|bin| {
+        power[*bin] > power[bin - 1]
+          && power.get(bin + 1).map_or(true, |next| power[*bin] >= *next)
+      })
+      .map(|bin| {
+        // A parabola through the peak and its neighbours places it between
+        // bins
+        let offset = match power.get(bin + 1) {
+          Some(next) => {
+            let curvature = power[bin - 1] - 2.0 * power[bin] + next;
+            if curvature < 0.0 {
+              0.5 * (power[bin - 1] - next) / curvature
+            } else {
+              0.0
+            }
+          }
+          None => 0.0,
+        };
+        let step = self.frequencies[1] - self.frequencies[0];
+        (1.0 / (self.frequencies[bin] + offset * step), power[bin])
+      })
+      .collect();
+    peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    peaks.truncate(count);
+    peaks
+  }
+}
+
+/// The values less their mean, as complex values zero-padded to `size`.
+fn centered(values: &[f64], size: usize) -> Vec<(f64, f64)> {
+  let mean = values.iter().sum::<f64>() / values.len() as f64;
+  values
+    .iter()
+    .map(|value| (value - mean, 0.0))
+    .chain(std::iter::repeat((0.0, 0.0)))
+    .take(size)
+    .collect()
+}
+
+/// An in-place radix-2 FFT of complex values as `(re, im)`, whose length must
+/// be a power of two. The inverse is scaled by the length.
+fn fft(values: &mut [(f64, f64)], inverse: bool) {
+  let n = values.len();
+  if n < 2 {
+    return;
+  }
+  let mut j = 0;
+  for i in 1..n {
+    let mut bit = n >> 1;
+    while j & bit != 0 {
+      j ^= bit;
+      bit >>= 1;
+    }
+    j |= bit;
+    if i < j {
+      values.swap(i, j);
+    }
+  }
+
+  let sign = if inverse { 1.0 } else { -1.0 };
+  let mut length = 2;
+  while length <= n {
+    let angle = sign * 2.0 * std::f64::consts::PI / length as f64;
+    let step = (angle.cos(), angle.sin());
+    for start in (0..n).step_by(length) {
+      let mut w = (1.0, 0.0);
+      for k in 0..length / 2 {
+        let (a, b) = (values[start + k], values[start + k + length / 2]);
+        let b = (b.0 * w.0 - b.1 * w.1, b.0 * w.1 + b.1 * w.0);
+        values[start + k] = (a.0 + b.0, a.1 + b.1);
+        values[start + k + length / 2] = (a.0 - b.0, a.1 - b.1);
+        w = (w.0 * step.0 - w.1 * step.1, w.0 * step.1 + w.1 * step.0);
+      }
+    }
+    length <<= 1;
+  }
+
+  if inverse {
+    for value in values.iter_mut() {
+      *value = (value.0 / n as f64, value.1 / n as f64);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+
+  /// Standard normal values from a fixed seed, by xorshift and Box-Muller.
+  fn normals(count: usize, mut seed: u64) -> Vec<f64> {
+    let mut uniform = move || {
+      seed ^= seed << 13;
+      seed ^= seed >> 7;
+      seed ^= seed << 17;
+      (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..count)
+      .map(|_| {
+        let (u, v) = (1.0 - uniform(), uniform());
+        (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+      })
+      .collect()
+  }
+
+  fn ar1(phi: f64, count: usize) -> Vec<f64> {
+    let mut x = 0.0;
+    normals(count, 42)
+      .into_iter()
+      .map(|noise| {
+        x = phi * x + noise;
+        x
+      })
+      .collect()
+  }
+
+  fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance
+  }
+
+  #[test]
+  fn fft_matches_direct_transform() {
+    let values: Vec<(f64, f64)> = (0..8)
+      .map(|i| (i as f64 * 0.5 - 1.0, (i * i) as f64 * 0.1))
+      .collect();
+    let mut buffer = values.clone();
+    fft(&mut buffer, false);
+    for (k, value) in buffer.iter().enumerate() {
+      let direct = values.iter().enumerate().fold((0.0, 0.0), |sum, (n, x)| {
+        let angle = -2.0 * PI * (k * n) as f64 / 8.0;
+        let (cos, sin) = (angle.cos(), angle.sin());
+        (sum.0 + x.0 * cos - x.1 * sin, sum.1 + x.0 * sin + x.1 * cos)
+      });
+      assert!(close(value.0, direct.0, 1e-9) && close(value.1, direct.1, 1e-9));
+    }
+    fft(&mut buffer, true);
+    for (value, original) in buffer.iter().zip(values.iter()) {
+      assert!(close(value.0, original.0, 1e-9));
+      assert!(close(value.1, original.1, 1e-9));
+    }
+  }
+
+  #[test]
+  fn autocorrelation_matches_direct_sum() {
+    let values = [1.0, 3.0, 2.0, 5.0, 4.0, 4.0, 1.0];
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let sum = |lag: usize| -> f64 {
+      (0..values.len() - lag)
+        .map(|i| (values[i] - mean) * (values[i + lag] - mean))
+        .sum()
+    };
+    let rho = autocorrelation(&values, 10);
+    assert_eq!(rho.len(), values.len());
+    for (lag, rho) in rho.iter().enumerate() {
+      assert!(close(*rho, sum(lag) / sum(0), 1e-12));
+    }
+  }
+
+  #[test]
+  fn ar1_autocorrelation_time() {
+    // For AR(1), tau is (1 + phi) / (1 - phi)
+    let values = ar1(0.9, 200_000);
+    let tau = integrated_autocorrelation_time(&values, 5.0);
+    assert!(close(tau, 19.0, 1.5), "tau {}", tau);
+    let ess = effective_sample_size(&values);
+    assert!(close(ess, 200_000.0 / tau, 1e-6));
+  }
+
+  #[test]
+  fn independent_autocorrelation_time() {
+    let tau = integrated_autocorrelation_time(&normals(100_000, 7), 5.0);
+    assert!(close(tau, 1.0, 0.05), "tau {}", tau);
+  }
+
+  #[test]
+  fn oscillating_series_count_as_independent() {
+    let alternating: Vec<f64> = (0..1000)
+      .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+      .collect();
+    assert_eq!(integrated_autocorrelation_time(&alternating, 5.0), 1.0);
+    assert_eq!(effective_sample_size(&alternating), 1000.0);
+    let sine: Vec<f64> =
+      (0..1000).map(|i| (PI * i as f64 / 2.0).sin()).collect();
+    assert_eq!(integrated_autocorrelation_time(&sine, 5.0), 1.0);
+  }
+
+  #[test]
+  fn short_series_have_no_autocorrelation_time() {
+    assert!(integrated_autocorrelation_time(&[], 5.0).is_nan());
+    assert!(integrated_autocorrelation_time(&[1.0], 5.0).is_nan());
+    assert!(integrated_autocorrelation_time(&[1.0, 2.0, 4.0], 5.0).is_nan());
+    assert!(effective_sample_size(&[1.0, 2.0, 4.0]).is_nan());
+    assert!(integrated_autocorrelation_time(&[2.0; 100], 5.0).is_nan());
+  }
+
+  #[test]
+  fn batch_means() {
+    let values: Vec<f64> = (0..10).map(|i| i as f64).collect();
+    let batches = BatchMeans::new(&values, 3);
+    // The oldest sample is dropped, leaving batches of 1..=3, 4..=6, 7..=9
+    assert_eq!((batches.batches, batches.batch_size), (3, 3));
+    assert!(close(batches.mean, 5.0, 1e-12));
+    assert!(close(batches.std_error, 3.0f64.sqrt(), 1e-12));
+    assert!(BatchMeans::new(&values, 1).std_error.is_nan());
+  }
+
+  #[test]
+  fn spectrum_finds_periods() {
+    let values: Vec<f64> = (0..1024)
+      .map(|i| {
+        let t = i as f64;
+        (2.0 * PI * t / 32.0).sin() + 0.3 * (2.0 * PI * t / 8.0).cos()
+      })
+      .collect();
+    let spectrum = Spectrum::new(&values, 0.5);
+    assert_eq!(spectrum.frequencies.len(), 513);
+    assert!(close(spectrum.frequencies[512], 1.0, 1e-12));
+    let periods = spectrum.dominant_periods(2);
+    // Periods are in units of the spacing
+    assert!(close(periods[0].0, 16.0, 1e-9), "{:?}", periods);
+    assert!(close(periods[1].0, 4.0, 1e-9), "{:?}", periods);
+    assert!(periods[0].1 > periods[1].1);
+  }
+}
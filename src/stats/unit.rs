@@ -0,0 +1,233 @@
+use std::fmt::{self, Display};
+
+/// How values of a unit are abbreviated when shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefixes {
+  /// Values are shown as they are.
+  None,
+  /// SI prefixes, from nano to tera, in steps of a thousand.
+  Si,
+  /// K, M and B for thousands, millions and billions, as for counts.
+  Count,
+}
+
+/// An SI prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefix {
+  Nano,
+  Micro,
+  Milli,
+  Kilo,
+  Mega,
+  Giga,
+  Tera,
+}
+
+impl Prefix {
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      Prefix::Nano => "n",
+      Prefix::Micro => "µ",
+      Prefix::Milli => "m",
+      Prefix::Kilo => "k",
+      Prefix::Mega => "M",
+      Prefix::Giga => "G",
+      Prefix::Tera => "T",
+    }
+  }
+
+  pub fn factor(&self) -> f64 {
+    match self {
+      Prefix::Nano => 1e-9,
+      Prefix::Micro => 1e-6,
+      Prefix::Milli => 1e-3,
+      Prefix::Kilo => 1e3,
+      Prefix::Mega => 1e6,
+      Prefix::Giga => 1e9,
+      Prefix::Tera => 1e12,
+    }
+  }
+
+  /// The prefix that shows `magnitude` with one to three digits before the
+  /// point, if any.
+  pub fn for_magnitude(magnitude: f64) -> Option<Self> {
+    let magnitude = magnitude.abs();
+    if magnitude == 0.0 || !magnitude.is_finite() {
+      return None;
+    }
+    match (magnitude.log10() / 3.0).floor() as i32 {
+      i32::MIN..=-3 => Some(Prefix::Nano),
+      -2 => Some(Prefix::Micro),
+      -1 => Some(Prefix::Milli),
+      0 => None,
+      1 => Some(Prefix::Kilo),
+      2 => Some(Prefix::Mega),
+      3 => Some(Prefix::Giga),
+      _ => Some(Prefix::Tera),
+    }
+  }
+}
+
+/// The unit of a statistic or of time. Units that are multiples of the same
+/// base, such as J and kJ or hours and days, convert between each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+  pub symbol: String,
+  /// The symbol of the base unit.
+  pub base: String,
+  /// How many base units make one of this unit.
+  pub factor: f64,
+  pub prefixes: Prefixes,
+}
+
+impl Unit {
+  /// A base unit shown with K, M and B, as untyped units are.
+  pub fn new(symbol: &str) -> Self {
+    Self {
+      symbol: symbol.into(),
+      base: symbol.into(),
+      factor: 1.0,
+      prefixes: Prefixes::Count,
+    }
+  }
+
+  /// A base unit shown with SI prefixes, such as J shown as kJ or MJ.
+  pub fn si(symbol: &str) -> Self {
+    Self {
+      prefixes: Prefixes::Si,
+      ..Self::new(symbol)
+    }
+  }
+
+  /// `factor` of another unit, such as a day of 24 hours.
+  pub fn multiple(symbol: &str, factor: f64, of: &Unit) -> Self {
+    Self {
+      symbol: symbol.into(),
+      base: of.base.clone(),
+      factor: factor * of.factor,
+      prefixes: Prefixes::None,
+    }
+  }
+
+  /// This unit with a fixed prefix, such as kJ for J.
+  pub fn prefixed(&self, prefix: Prefix) -> Self {
+    Self::multiple(
+      &format!("{}{}", prefix.symbol(), self.symbol),
+      prefix.factor(),
+      self,
+    )
+  }
+
+  pub fn prefixes(mut self, prefixes: Prefixes) -> Self {
+    self.prefixes = prefixes;
+    self
+  }
+
+  pub fn seconds() -> Self {
+    Self::si("s")
+  }
+
+  pub fn minutes() -> Self {
+    Self::multiple("min", 60.0, &Self::seconds())
+  }
+
+  pub fn hours() -> Self {
+    Self::multiple("h", 3600.0, &Self::seconds())
+  }
+
+  pub fn days() -> Self {
+    Self::multiple("d", 86400.0, &Self::seconds())
+  }
+
+  pub fn converts_to(&self, other: &Unit) -> bool {
+    self.base == other.base
+  }
+
+  /// A value of this unit in another, if they share a base.
+  pub fn convert(&self, value: f64, to: &Unit) -> Option<f64> {
+    if !self.converts_to(to) {
+      None
+    } else if self.factor == to.factor {
+      Some(value)
+    } else {
+      Some(value * self.factor / to.factor)
+    }
+  }
+
+  /// A value with its unit, abbreviated by the unit's prefixes.
+  pub fn format(&self, value: f64, precision: usize) -> String {
+    let (value, symbol) = match self.prefixes {
+      Prefixes::Si => match Prefix::for_magnitude(value) {
+        Some(prefix) => (
+          value / prefix.factor(),
+          format!("{}{}", prefix.symbol(), self.symbol),
+        ),
+        None => (value, self.symbol.clone()),
+      },
+      Prefixes::Count => {
+        let (value, suffix) = count_suffix(value);
+        (value, format!("{}{}", suffix, self.symbol).trim().into())
+      }
+      Prefixes::None => (value, self.symbol.clone()),
+    };
+    if symbol.is_empty() {
+      format!("{:.*}", precision, value)
+    } else {
+      format!("{:.*} {}", precision, value, symbol)
+    }
+  }
+
+  /// The unit to head an axis spanning `min..max` with, prefixed to suit its
+  /// largest magnitude when SI prefixes apply.
+  pub fn axis_unit(&self, min: f64, max: f64) -> Unit {
+    match self.prefixes {
+      Prefixes::Si => {
+        match Prefix::for_magnitude(f64::max(min.abs(), max.abs())) {
+          Some(prefix) => self.prefixed(prefix),
+          None => self.clone().prefixes(Prefixes::None),
+        }
+      }
+      _ => self.clone(),
+    }
+  }
+
+  /// An axis label for a value of this unit, which leaves the symbol to the
+  /// axis description.
+  pub fn axis_label(&self, value: f64) -> String {
+    match self.prefixes {
+      Prefixes::Count => {
+        let (value, suffix) = count_suffix(value);
+        format!("{:.2}{}", value, suffix.trim_end())
+      }
+      _ => format!("{:.2}", value),
+    }
+  }
+}
+
+/// A count in thousands, millions or billions, with the suffix to show it.
+fn count_suffix(value: f64) -> (f64, &'static str) {
+  match value.abs() {
+    v if v >= 1_000_000_000.0 => (value / 1_000_000_000.0, " B "),
+    v if v >= 1_000_000.0 => (value / 1_000_000.0, " M "),
+    v if v >= 1_000.0 => (value / 1_000.0, " K "),
+    _ => (value, ""),
+  }
+}
+
+impl Display for Unit {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.symbol)
+  }
+}
+
+impl From<&str> for Unit {
+  fn from(symbol: &str) -> Self {
+    Self::new(symbol)
+  }
+}
+
+impl From<String> for Unit {
+  fn from(symbol: String) -> Self {
+    Self::new(&symbol)
+  }
+}
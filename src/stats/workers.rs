@@ -0,0 +1,162 @@
+use super::Statistics;
+use std::{
+  any::Any,
+  collections::BTreeMap,
+  panic::{self, AssertUnwindSafe},
+  sync::{
+    mpsc::{self, Receiver, SyncSender},
+    Arc, Mutex,
+  },
+  thread::{self, JoinHandle},
+};
+
+/// Statistics that can be derived off the simulation thread, from a snapshot
+/// of the state. The snapshot can be a clone of the state or a lighter view
+/// holding only what `derive_snapshot` reads.
+pub trait AsyncStatistics<T>: Statistics<T> + Send + 'static {
+  type TSnapshot: Send + 'static;
+
+  fn snapshot(state: &T) -> Self::TSnapshot;
+
+  /// Derives a sample, where only the groups flagged in `due` are needed as
+  /// in `Statistics::derive_groups`.
+  fn derive_snapshot(snapshot: Self::TSnapshot, due: &[bool]) -> Self;
+}
+
+type Job<S> = Box<dyn FnOnce() -> S + Send>;
+
+/// A job's statistics, or the payload of its panic.
+type JobResult<S> = Result<S, Box<dyn Any + Send>>;
+
+fn snapshot_job<T, S: AsyncStatistics<T>>(state: &T, due: Vec<bool>) -> Job<S> {
+  let snapshot = S::snapshot(state);
+  Box::new(move || S::derive_snapshot(snapshot, &due))
+}
+
+/// A derived sample, and whether it was scheduled to be recorded rather than
+/// only summarized.
+pub(crate) struct Derived<S> {
+  pub tick: usize,
+  pub record: bool,
+  pub stats: S,
+}
+
+/// Threads deriving statistics from snapshots, handing results back in the
+/// order they were submitted.
+pub(crate) struct DerivePool<T, S> {
+  snapshot: fn(&T, Vec<bool>) -> Job<S>,
+  jobs: Option<SyncSender<(usize, Job<S>)>>,
+  results: Receiver<(usize, JobResult<S>)>,
+  submitted: Vec<(usize, bool)>,
+  ready: BTreeMap<usize, S>,
+  next_seq: usize,
+  next_ready: usize,
+  threads: Vec<JoinHandle<()>>,
+}
+
+impl<T, S: AsyncStatistics<T>> DerivePool<T, S> {
+  pub fn new(workers: usize) -> Self {
+    let workers = workers.max(1);
+    // Bounded, so a simulation far ahead of its workers waits rather than
+    // piling up snapshots
+    let (jobs, job_receiver) =
+      mpsc::sync_channel::<(usize, Job<S>)>(workers * 2);
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let (result_sender, results) = mpsc::channel();
+    let threads = (0..workers)
+      .map(|_| {
+        let job_receiver = job_receiver.clone();
+        let result_sender = result_sender.clone();
+        thread::spawn(move || loop {
+          let job = job_receiver.lock().unwrap().recv();
+          let (seq, job) = match job {
+            Ok(job) => job,
+            Err(_) => return,
+          };
+          // A panicking job is handed back to panic on the caller's thread,
+          // which would otherwise wait for its result forever
+          let result = panic::catch_unwind(AssertUnwindSafe(job));
+          if result_sender.send((seq, result)).is_err() {
+            return;
+          }
+        })
+      })
+      .collect();
+    Self {
+      snapshot: snapshot_job::<T, S>,
+      jobs: Some(jobs),
+      results,
+      submitted: vec![],
+      ready: BTreeMap::new(),
+      next_seq: 0,
+      next_ready: 0,
+      threads,
+    }
+  }
+}
+
+impl<T, S> DerivePool<T, S> {
+  pub fn submit(
+    &mut self,
+    state: &T,
+    tick: usize,
+    record: bool,
+    due: Vec<bool>,
+  ) {
+    let job = (self.snapshot)(state, due);
+    if let Some(jobs) = &self.jobs {
+      jobs.send((self.next_seq, job)).unwrap();
+    }
+    self.submitted.push((tick, record));
+    self.next_seq += 1;
+  }
+
+  /// The results that are next in order, waiting for every outstanding one
+  /// when `wait` is set. Panics from deriving are resumed here.
+  pub fn take_ready(&mut self, wait: bool) -> Vec<Derived<S>> {
+    while self.next_ready + self.ready.len() < self.next_seq {
+      let result = if wait {
+        self.results.recv().ok()
+      } else {
+        self.results.try_recv().ok()
+      };
+      match result {
+        Some((seq, Ok(stats))) => {
+          self.ready.insert(seq, stats);
+        }
+        Some((_, Err(payload))) => panic::resume_unwind(payload),
+        None => break,
+      }
+    }
+
+    let mut derived = vec![];
+    while let Some(stats) = self.ready.remove(&self.next_ready) {
+      let (tick, record) = self.submitted[self.next_ready - self.offset()];
+      derived.push(Derived {
+        tick,
+        record,
+        stats,
+      });
+      self.next_ready += 1;
+    }
+    // Forget the bookkeeping of samples that have been handed back
+    let done = self.next_ready - self.offset();
+    self.submitted.drain(..done);
+    derived
+  }
+
+  /// The sequence number of the first entry in `submitted`.
+  fn offset(&self) -> usize {
+    self.next_seq - self.submitted.len()
+  }
+}
+
+impl<T, S> Drop for DerivePool<T, S> {
+  fn drop(&mut self) {
+    // Closing the queue stops the workers once it drains
+    self.jobs = None;
+    for thread in self.threads.drain(..) {
+      let _ = thread.join();
+    }
+  }
+}